@@ -0,0 +1,81 @@
+use clap::ArgMatches;
+use colored::Colorize;
+use regex::Regex;
+
+use crate::commands::CommandOpts;
+use crate::s3::ACL;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let (action, action_matches) = match sub_matches.subcommand() {
+    Some((action, action_matches)) => (action, action_matches),
+    None => unreachable!(),
+  };
+
+  let bkt = crate::commands::CmdArgs::from(action_matches).get_bucket();
+  let opts = <AclOpts as CommandOpts>::from(action_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  let result = match action {
+    "get" => bkt.acl_get(opts).await,
+    "set" => bkt.acl_set(opts).await,
+    _ => unreachable!(),
+  };
+
+  if let Err(e) = result {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct AclOpts {
+  pub verbose: bool,
+  pub recursive: bool,
+  pub dry_run: bool,
+  pub delimiter: char,
+  pub path: String,
+  pub exclude: Vec<Regex>,
+  pub acl: Option<ACL>,
+}
+
+impl CommandOpts for AclOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+    let recursive = sub_matches.get_flag("recursive");
+
+    // Only `set` changes anything, so only it has a dry run
+    let dry_run = sub_matches.try_get_one::<bool>("dry-run")
+       .ok()
+       .flatten()
+       .cloned()
+       .unwrap_or(false);
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let path = args.parse_prefix("PATH", false).unwrap();
+
+    let exclude = args.parse_exclude();
+
+    let acl = match sub_matches.try_get_one::<String>("acl") {
+      Ok(_) => args.parse_acl(),
+      Err(_) => None,
+    };
+
+    Self {
+      verbose,
+      recursive,
+      dry_run,
+      delimiter,
+      path,
+      exclude,
+      acl,
+    }
+  }
+}
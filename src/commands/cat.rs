@@ -0,0 +1,98 @@
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::commands::CommandOpts;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <CatOpts as CommandOpts>::from(sub_matches);
+
+  // Stdout carries the object, so anything else goes to stderr
+  if opts.verbose {
+    eprintln!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.cat(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct CatOpts {
+  pub verbose: bool,
+  pub delimiter: char,
+  pub path: String,
+  /// Inclusive byte range, an open end reads until the end of the object
+  pub range: Option<(u64, Option<u64>)>,
+}
+
+impl CatOpts {
+  /// Value of the Range header for the requested bytes
+  pub fn range_header(&self) -> Option<String> {
+    self.range.map(|(start, end)| match end {
+      Some(end) => format!("bytes={}-{}", start, end),
+      None => format!("bytes={}-", start),
+    })
+  }
+}
+
+impl CommandOpts for CatOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let path = args.parse_prefix("PATH", false).unwrap();
+
+    let range = sub_matches.get_one::<String>("range").map(|value| {
+      parse_range(value).unwrap_or_else(|| {
+        eprintln!("{} {} is not a valid range, expected start-end", "error:".red(), value);
+        std::process::exit(1);
+      })
+    });
+
+    Self {
+      verbose,
+      delimiter,
+      path,
+      range,
+    }
+  }
+}
+
+/// Parses an inclusive byte range such as `0-1023` or `1024-`
+fn parse_range(value: &str) -> Option<(u64, Option<u64>)> {
+  let (start, end) = value.trim().split_once('-')?;
+  let start = start.parse::<u64>().ok()?;
+
+  if end.is_empty() {
+    return Some((start, None));
+  }
+
+  let end = end.parse::<u64>().ok()?;
+  if end < start {
+    return None;
+  }
+
+  Some((start, Some(end)))
+}
+
+#[cfg(test)]
+mod cat_tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_range() {
+    assert_eq!(parse_range("0-1023"), Some((0, Some(1023))));
+    assert_eq!(parse_range("1024-"), Some((1024, None)));
+    assert_eq!(parse_range("10-10"), Some((10, Some(10))));
+    assert_eq!(parse_range("20-10"), None);
+    assert_eq!(parse_range("-500"), None);
+    assert_eq!(parse_range("abc"), None);
+  }
+}
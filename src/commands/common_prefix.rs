@@ -1,5 +1,9 @@
 use colored::Colorize;
+use tokio_stream::StreamExt;
 
+use crate::commands::list::ListPrinter;
+use crate::s3::bucket::list_prefix;
+use crate::s3::bucket::output::ListEntry;
 use crate::s3::ParsedS3Url;
 
 pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
@@ -22,14 +26,50 @@ pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
 
   let delimiter = args.parse_delimiter();
 
-  let result = bkt.prefixes(&prefix, &delimiter).await?;
+  let output = args.parse_output();
 
-  if result.common_prefixes.is_some() {
-    for prefix in result.common_prefixes.unwrap() {
-      // <CREATED
-      println!("{}", prefix.prefix.unwrap());
+  let depth = args.parse_depth().unwrap_or(1);
+  if depth == 0 {
+    eprintln!("{} Depth must be at least 1", "error:".red());
+    std::process::exit(1);
+  }
+
+  let parsed = ParsedS3Url::parse_from(&prefix, &delimiter)?;
+
+  let mut printer = ListPrinter::new(output, false);
+
+  // Every level is listed once the one above it was printed, so the deepest level is never
+  // expanded and the prefixes are printed as soon as a page of them arrives
+  let result = async {
+    // The common prefixes are listed as they are, a url would lose their empty segments
+    let mut level = vec![list_prefix(&parsed, delimiter)];
+    for current in 1..=depth {
+      let mut next = Vec::new();
+
+      for key_prefix in level {
+        let mut pages = bkt.prefixes(parsed.bucket_name(), &key_prefix, &delimiter);
+        while let Some(page) = pages.next().await {
+          for common_prefix in page? {
+            printer.print(ListEntry::from_prefix(&common_prefix))?;
+            if current < depth {
+              next.push(common_prefix);
+            }
+          }
+        }
+      }
+
+      level = next;
     }
+
+    anyhow::Ok(())
+  }.await;
+
+  if let Err(e) = result {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
   }
 
+  printer.finish();
+
   Ok(())
 }
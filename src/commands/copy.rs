@@ -3,7 +3,9 @@ use colored::Colorize;
 use regex::Regex;
 
 use crate::commands::CommandOpts;
-use crate::s3::ParsedS3Url;
+use crate::s3::bucket::multipart::{DEFAULT_CONCURRENCY, DEFAULT_PART_SIZE, MIN_PART_SIZE};
+use crate::s3::bucket::transfer::TransferOpts;
+use crate::s3::{ACL, ParsedS3Url};
 
 pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
   let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
@@ -13,6 +15,11 @@ pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
     println!("{:?}", opts);
   }
 
+  if let Err(e) = bkt.cp(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
   Ok(())
 }
 
@@ -25,6 +32,31 @@ pub struct CopyOpts {
   pub from: String,
   pub to: String,
   pub exclude: Vec<Regex>,
+  pub part_size: u64,
+  pub concurrency: usize,
+  pub checksum: bool,
+  pub acl: Option<ACL>,
+  /// Set by `mv`, every source is removed once its copy has been verified
+  pub remove_source: bool,
+}
+
+impl CopyOpts {
+  /// Settings applied to every transferred file
+  pub fn transfer(&self) -> TransferOpts {
+    TransferOpts {
+      part_size: self.part_size,
+      concurrency: self.concurrency,
+      acl: self.acl,
+    }
+  }
+
+  /// Name of the action printed for every transferred file
+  pub fn action<'a>(&self, action: &'a str) -> &'a str {
+    match self.remove_source {
+      true => "move",
+      false => action,
+    }
+  }
 }
 
 impl CommandOpts for CopyOpts {
@@ -41,12 +73,24 @@ impl CommandOpts for CopyOpts {
        .unwrap_or_else(|| &false)
        .clone();
 
+    let checksum = sub_matches.get_flag("checksum");
+
     let args = crate::commands::CmdArgs::from(sub_matches);
 
     let delimiter = args.parse_delimiter();
 
     let exclude = args.parse_exclude();
 
+    let part_size = args.parse_size("part-size", DEFAULT_PART_SIZE);
+    if part_size < MIN_PART_SIZE {
+      eprintln!("{} Part size must be at least 5MiB", "error:".red());
+      std::process::exit(1);
+    }
+
+    let concurrency = args.parse_count("concurrency", DEFAULT_CONCURRENCY);
+
+    let acl = args.parse_acl();
+
     let from = read_required_string(&sub_matches, "FROM");
     let to = read_required_string(&sub_matches, "TO");
 
@@ -64,6 +108,11 @@ impl CommandOpts for CopyOpts {
       from,
       to,
       exclude,
+      part_size,
+      concurrency,
+      checksum,
+      acl,
+      remove_source: false,
     }
   }
 }
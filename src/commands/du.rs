@@ -2,6 +2,10 @@ use std::process;
 
 use colored::Colorize;
 
+use crate::commands::output::{print_value, OutputFormat, RowWriter};
+use crate::s3::bucket::output::{format_size, DU_COLUMNS};
+use crate::s3::ParsedS3Url;
+
 pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
   let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
   let opts = DuOpts::from(&sub_matches);
@@ -17,12 +21,37 @@ pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
     process::exit(1);
   }
 
-  let size = result.unwrap() as f64;
+  let result = result.unwrap();
+
+  if opts.output.is_structured() {
+    return print_value(opts.output, &result);
+  }
+
+  // Prefixes are shown as urls like the total, with the bucket followed by the delimiter
+  let bucket = ParsedS3Url::parse_from(&opts.prefix, &opts.delimiter)?;
+  let url_of = |prefix: &str| format!("s3://{}{}{}", bucket.bucket_name(), opts.delimiter, prefix);
+
+  if opts.output.is_tabular() {
+    let mut writer = RowWriter::new(opts.output, &DU_COLUMNS, &[1, 2]);
+    for prefix in &result.prefixes {
+      let mut row = prefix.row(opts.human_readable);
+      row[0] = url_of(&prefix.prefix);
+      writer.write(row);
+    }
+    writer.write(result.row(&opts.prefix, opts.human_readable));
+    writer.finish();
+    return Ok(());
+  }
+
+  // Like `du -d`, every prefix is followed by the total of the given path
+  for prefix in &result.prefixes {
+    println!("{}\t{}", format_size(prefix.total_size_bytes, opts.human_readable), url_of(&prefix.prefix));
+  }
 
-  if opts.human_readable {
-    println!("{}", human_bytes::human_bytes(size));
+  if result.prefixes.is_empty() {
+    println!("{}", format_size(result.total_size_bytes, opts.human_readable));
   } else {
-    println!("{}", size.to_string());
+    println!("{}\t{}", format_size(result.total_size_bytes, opts.human_readable), opts.prefix);
   }
 
   Ok(())
@@ -36,6 +65,11 @@ pub struct DuOpts {
   pub human_readable: bool,
   pub delimiter: char,
   pub prefix: String,
+  pub output: OutputFormat,
+  /// Number of key segments after the prefix totals are grouped by
+  pub depth: Option<usize>,
+  /// Number of shards of the keyspace listed at the same time
+  pub list_concurrency: usize,
 }
 
 impl DuOpts {
@@ -62,6 +96,12 @@ impl DuOpts {
 
     let prefix = args.parse_prefix("PREFIX", false).unwrap();
 
+    let output = args.parse_output();
+
+    let depth = args.parse_depth();
+
+    let list_concurrency = args.parse_count("list-concurrency", 1);
+
     Self {
       verbose,
       show_total,
@@ -69,6 +109,9 @@ impl DuOpts {
       human_readable,
       delimiter,
       prefix,
+      output,
+      depth,
+      list_concurrency,
     }
   }
 }
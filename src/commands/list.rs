@@ -1,12 +1,12 @@
-use std::collections::HashMap;
-
 use clap::ArgMatches;
 use colored::Colorize;
 use regex::Regex;
+use tokio_stream::StreamExt;
 
 use crate::commands::CommandOpts;
-use crate::s3::content::S3Directory;
-use crate::utc_datetime;
+use crate::commands::output::{EntryWriter, OutputFormat, RowWriter};
+use crate::s3::bucket::listing::LevelListing;
+use crate::s3::bucket::output::{format_size, LIST_COLUMNS, LIST_SIZE_COLUMN, ListEntry};
 
 pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
   let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
@@ -16,84 +16,141 @@ pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
     println!("{:?}", opts);
   }
 
+  let mut printer = ListPrinter::new(opts.output, opts.human_readable);
+
   // if the path wasn't defined we're going to list the list of buckets
   if opts.path.is_none() {
     let result = bkt.bkt_ls().await?;
-    for bucket in result.buckets.unwrap() {
-      // <creation_date> <bucket_name>
-      // 2021-01-01T00:00:00.000Z bucket-name
-      println!(
-        "{} {}",
-        utc_datetime(bucket.creation_date.unwrap()),
-        bucket.name.unwrap()
-      );
+    for bucket in result.buckets().unwrap_or_default() {
+      printer.print(ListEntry::from_bucket(bucket))?;
     }
+    printer.finish();
     return Ok(());
   }
 
-  let result = bkt.ls(opts.clone()).await;
+  let path = opts.path.clone().unwrap();
+
+  // Without their sizes the directories are the common prefixes of the level, which spares
+  // listing every object below it
+  if !opts.recursive && !opts.dir_sizes {
+    let result = async {
+      let mut pages = bkt.level_pages(&path, &opts.delimiter)?;
+      while let Some(page) = pages.next().await {
+        let page = page?;
+        for directory in page.directories {
+          printer.print(ListEntry::from_directory_name(&directory))?;
+        }
+        for object in page.objects {
+          printer.print(ListEntry::from_object(&object))?;
+        }
+      }
+      anyhow::Ok(())
+    }.await;
+
+    if let Err(e) = result {
+      eprintln!("{} {:?}", "error:".red(), e.to_string());
+      std::process::exit(1);
+    }
 
-  if let Err(e) = result {
-    eprintln!("{} {:?}", "error:".red(), e.to_string());
-    std::process::exit(1);
+    printer.finish();
+    return Ok(());
   }
 
-  let result = result.unwrap();
-  if opts.verbose {
-    println!("{:?}", result);
-  }
+  // Objects are printed page by page as they arrive, directories once every page was seen
+  let result = async {
+    let mut pages = bkt.sharded_object_pages(&path, &opts.delimiter, opts.list_concurrency)?;
+    let mut listing = LevelListing::new(&path, opts.delimiter, opts.recursive)?;
+
+    while let Some(page) = pages.next().await {
+      for object in page? {
+        if let Some(object) = listing.push(object) {
+          printer.print(ListEntry::from_object(&object))?;
+        }
+      }
+    }
 
-  // if it was recursive we just need to list given files
-  if opts.recursive {
-    print_objects(&result.objects, opts.human_readable);
-    return Ok(());
+    for directory in listing.into_directories() {
+      printer.print(ListEntry::from_directory(&directory))?;
+    }
+
+    anyhow::Ok(())
+  }.await;
+
+  if let Err(e) = result {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
   }
 
-  print_directories(&result.directories, opts.human_readable);
-  print_objects(&result.objects, opts.human_readable);
+  printer.finish();
 
   Ok(())
 }
 
-/// Prints table of objects to stdout
-fn print_objects(objs: &Vec<aws_sdk_s3::types::Object>, human_size: bool) {
-  for object in objs {
-    // <last_modified> <bytes> <object_key>
-    // 2021-01-01T00:00:00.000Z 6651351 object-key
-    // 2021-01-01T00:00:00.000Z  60.9KB object-key
+/// Prints the entries of a listing one by one in the chosen output format
+pub(crate) struct ListPrinter {
+  human_size: bool,
+  sink: ListSink,
+}
+
+enum ListSink {
+  Text,
+  Entries(EntryWriter),
+  Rows(RowWriter),
+}
 
-    let size = match human_size {
-      true => human_bytes::human_bytes(object.size as f64),
-      _ => object.size.to_string()
+impl ListPrinter {
+  pub(crate) fn new(output: OutputFormat, human_size: bool) -> Self {
+    let sink = match output {
+      OutputFormat::Text => ListSink::Text,
+      OutputFormat::Json | OutputFormat::Ndjson => ListSink::Entries(EntryWriter::new(output)),
+      OutputFormat::Csv | OutputFormat::Table => {
+        ListSink::Rows(RowWriter::new(output, &LIST_COLUMNS, &[LIST_SIZE_COLUMN]))
+      }
     };
 
-    println!(
-      "{} {} {}",
-      utc_datetime(object.last_modified.unwrap()),
-      size,
-      object.key.as_ref().unwrap()
-    );
+    Self { human_size, sink }
   }
-}
 
-/// Prints table of directories to stdout
-fn print_directories(dirs: &HashMap<String, S3Directory>, human_size: bool) {
-  for directory in dirs.values() {
-    // <last_modified> DIR <bytes> <object_key>
-    // 2021-01-01T00:00:00.000Z DIR  6651351  object-key
-    // 2021-01-01T00:00:00.000Z DIR   60.9KB  object-key
+  pub(crate) fn print(&mut self, entry: ListEntry) -> anyhow::Result<()> {
+    match &mut self.sink {
+      ListSink::Text => println!("{}", text_line(&entry, self.human_size)),
+      ListSink::Entries(writer) => writer.write(&entry)?,
+      ListSink::Rows(writer) => writer.write(entry.row(self.human_size)),
+    }
+    Ok(())
+  }
 
-    let size = match human_size {
-      true => human_bytes::human_bytes(directory.size() as f64),
-      _ => directory.size().to_string()
-    };
+  pub(crate) fn finish(self) {
+    match self.sink {
+      ListSink::Text => {}
+      ListSink::Entries(writer) => writer.finish(),
+      ListSink::Rows(writer) => writer.finish(),
+    }
+  }
+}
 
-    println!(
-      "{} DIR {} {}",
-      utc_datetime(directory.last_modified()),
-      size,
-      directory.name
-    );
+/// Formats an entry as a line of the text output
+fn text_line(entry: &ListEntry, human_size: bool) -> String {
+  match entry {
+    // <creation_date> <bucket_name>
+    // 2021-01-01T00:00:00Z bucket-name
+    ListEntry::Bucket { name, creation_date } => {
+      format!("{} {}", creation_date.clone().unwrap_or_default(), name)
+    }
+    // <last_modified> DIR <bytes> <directory_name>
+    // 2021-01-01T00:00:00Z DIR 6651351 directory-name
+    //                      DIR directory-name
+    ListEntry::Directory { name, size: Some(size), last_modified, .. } => {
+      format!("{} DIR {} {}", last_modified.clone().unwrap_or_default(), format_size(*size, human_size), name)
+    }
+    ListEntry::Directory { name, .. } => format!("{:20} DIR {}", "", name),
+    // <last_modified> <bytes> <object_key>
+    // 2021-01-01T00:00:00Z 6651351 object-key
+    // 2021-01-01T00:00:00Z 60.9 KB object-key
+    ListEntry::Object { key, size, last_modified, .. } => {
+      format!("{} {} {}", last_modified.clone().unwrap_or_default(), format_size(*size as usize, human_size), key)
+    }
+    ListEntry::Prefix { prefix } => prefix.to_string(),
   }
 }
 
@@ -106,6 +163,11 @@ pub struct ListOpts {
   pub delimiter: char,
   pub path: Option<String>,
   pub exclude: Vec<Regex>,
+  pub output: OutputFormat,
+  /// List every object under the path to show the size of the directories
+  pub dir_sizes: bool,
+  /// Number of shards of the keyspace listed at the same time
+  pub list_concurrency: usize,
 }
 
 impl CommandOpts for ListOpts {
@@ -134,6 +196,12 @@ impl CommandOpts for ListOpts {
 
     let exclude = args.parse_exclude();
 
+    let output = args.parse_output();
+
+    let dir_sizes = sub_matches.get_flag("dir-sizes");
+
+    let list_concurrency = args.parse_count("list-concurrency", 1);
+
     Self {
       verbose,
       recursive,
@@ -142,6 +210,9 @@ impl CommandOpts for ListOpts {
       delimiter,
       path,
       exclude,
+      output,
+      dir_sizes,
+      list_concurrency,
     }
   }
 }
@@ -0,0 +1,52 @@
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::commands::CommandOpts;
+use crate::s3::ACL;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <MakeBucketOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.mb(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct MakeBucketOpts {
+  pub verbose: bool,
+  pub path: String,
+  pub acl: Option<ACL>,
+}
+
+impl CommandOpts for MakeBucketOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let path = args.parse_prefix("BUCKET", false).unwrap();
+
+    let acl = args.parse_acl();
+    if let Some(acl) = acl {
+      if !acl.is_bucket_acl() {
+        eprintln!("{} {} can only be applied to objects", "error:".red(), acl.to_string());
+        std::process::exit(1);
+      }
+    }
+
+    Self {
+      verbose,
+      path,
+      acl,
+    }
+  }
+}
@@ -2,17 +2,29 @@ use clap::ArgMatches;
 use colored::Colorize;
 use regex::Regex;
 
+use crate::commands::output::OutputFormat;
 use crate::s3::bucket::Bucket;
 use crate::s3::credentials::Credentials;
-use crate::s3::ParsedS3Url;
+use crate::s3::{ACL, ParsedS3Url};
 use crate::s3::profile::ProfileSet;
 use crate::utils::validator;
 
+pub mod acl;
+pub mod cat;
 pub mod common_prefix;
 pub mod copy;
 pub mod du;
 pub mod list;
+pub mod make_bucket;
 pub mod make_profile;
+pub mod mv;
+pub mod output;
+pub mod presign;
+pub mod remove;
+pub mod remove_bucket;
+pub mod stat;
+pub mod sync;
+pub mod tree;
 
 pub struct CmdArgs {
   pub args: ArgMatches,
@@ -115,15 +127,72 @@ impl CmdArgs {
     Some(prefix.clone())
   }
 
+  /// Reads a size argument such as `8388608`, `64M` or `1GiB`, returns the default when not given
+  pub fn parse_size(&self, id: &str, default: u64) -> u64 {
+    match self.args.get_one::<String>(id) {
+      Some(value) => parse_byte_size(value).unwrap_or_else(|| {
+        eprintln!("{} {} is not a valid size", "error:".red(), value);
+        std::process::exit(1);
+      }),
+      None => default
+    }
+  }
+
+  /// Reads a positive number argument, returns the default when not given
+  pub fn parse_count(&self, id: &str, default: usize) -> usize {
+    match self.args.get_one::<String>(id) {
+      Some(value) => match value.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+          eprintln!("{} {} must be a positive number", "error:".red(), id);
+          std::process::exit(1);
+        }
+      },
+      None => default
+    }
+  }
+
+  /// Reads the global output format, text is used when not given
+  pub fn parse_output(&self) -> OutputFormat {
+    match self.args.try_get_one::<String>("output").ok().flatten() {
+      Some(name) => OutputFormat::from_name(name).unwrap_or_else(|| {
+        eprintln!("{} {} is not a valid output format, expected one of: text, json, ndjson, csv, table", "error:".red(), name);
+        std::process::exit(1);
+      }),
+      None => OutputFormat::Text
+    }
+  }
+
+  /// Reads the depth argument, zero is allowed unlike other counts
+  pub fn parse_depth(&self) -> Option<usize> {
+    self.args.get_one::<String>("depth").map(|value| {
+      value.parse::<usize>().unwrap_or_else(|_| {
+        eprintln!("{} depth must be a number", "error:".red());
+        std::process::exit(1);
+      })
+    })
+  }
+
+  /// Reads the canned ACL argument, returns `None` when not given
+  pub fn parse_acl(&self) -> Option<ACL> {
+    self.args.get_one::<String>("acl").map(|name| {
+      ACL::from_name(name).unwrap_or_else(|| {
+        let names = ACL::all().iter().map(|acl| acl.to_string()).collect::<Vec<_>>();
+        eprintln!("{} {} is not a valid ACL, expected one of: {}", "error:".red(), name, names.join(", "));
+        std::process::exit(1);
+      })
+    })
+  }
+
   pub fn parse_exclude(&self) -> Vec<Regex> {
     // The exclude expression must be a valid Regex
-    let exclude = self.args.get_many("exclude");
+    let exclude = self.args.get_many::<String>("exclude");
     if exclude.is_none() {
       let no_no_args: Vec<Regex> = Vec::new();
       return no_no_args;
     }
 
-    let exclude = exclude.unwrap().copied();
+    let exclude = exclude.unwrap();
     let mut exclude_regexes: Vec<Regex> = Vec::new();
     for exp in exclude {
       match Regex::new(exp) {
@@ -142,3 +211,37 @@ impl CmdArgs {
 pub trait CommandOpts {
   fn from(sub_matches: &clap::ArgMatches) -> Self;
 }
+
+/// Parses a size with an optional binary unit suffix (K, M, G, T), e.g. `512K`, `64MiB` or `1GB`
+pub fn parse_byte_size(value: &str) -> Option<u64> {
+  let value = value.trim();
+  let split_at = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+  let (number, unit) = value.split_at(split_at);
+  let number = number.parse::<u64>().ok()?;
+
+  let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+    "" | "B" => 1,
+    "K" | "KB" | "KIB" => 1024,
+    "M" | "MB" | "MIB" => 1024 * 1024,
+    "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+    "T" | "TB" | "TIB" => 1024 * 1024 * 1024 * 1024,
+    _ => return None
+  };
+
+  number.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod commands_tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_byte_size() {
+    assert_eq!(parse_byte_size("1024"), Some(1024));
+    assert_eq!(parse_byte_size("8M"), Some(8 * 1024 * 1024));
+    assert_eq!(parse_byte_size("64MiB"), Some(64 * 1024 * 1024));
+    assert_eq!(parse_byte_size("1gb"), Some(1024 * 1024 * 1024));
+    assert_eq!(parse_byte_size("M"), None);
+    assert_eq!(parse_byte_size("12X"), None);
+  }
+}
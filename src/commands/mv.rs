@@ -0,0 +1,20 @@
+use colored::Colorize;
+
+use crate::commands::CommandOpts;
+use crate::commands::copy::CopyOpts;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <CopyOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.mv(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+
+/// Format of what listing commands print to stdout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+  Text,
+  Json,
+  Ndjson,
+  Csv,
+  Table,
+}
+
+impl OutputFormat {
+  /// Finds a format by its name, e.g. `ndjson`
+  pub fn from_name(name: &str) -> Option<OutputFormat> {
+    match name.to_lowercase().as_str() {
+      "text" => Some(OutputFormat::Text),
+      "json" => Some(OutputFormat::Json),
+      "ndjson" => Some(OutputFormat::Ndjson),
+      "csv" => Some(OutputFormat::Csv),
+      "table" => Some(OutputFormat::Table),
+      _ => None
+    }
+  }
+
+  /// Whether the output is meant for other programs rather than people
+  pub fn is_structured(&self) -> bool {
+    matches!(self, OutputFormat::Json | OutputFormat::Ndjson)
+  }
+
+  /// Whether the output is made of rows with a fixed set of columns
+  pub fn is_tabular(&self) -> bool {
+    matches!(self, OutputFormat::Csv | OutputFormat::Table)
+  }
+}
+
+/// Prints entries as soon as they're given. With `ndjson` every entry is a line of its own, with
+/// `json` they're written as the elements of a single array which is closed by [EntryWriter::finish]
+pub struct EntryWriter {
+  format: OutputFormat,
+  written: usize,
+}
+
+impl EntryWriter {
+  pub fn new(format: OutputFormat) -> Self {
+    Self {
+      format,
+      written: 0,
+    }
+  }
+
+  pub fn write<T: Serialize>(&mut self, entry: &T) -> anyhow::Result<()> {
+    let line = serde_json::to_string(entry)?;
+
+    match self.format {
+      OutputFormat::Json => {
+        let separator = if self.written == 0 { "[" } else { "," };
+        println!("{}\n  {}", separator, line);
+      }
+      _ => println!("{}", line),
+    }
+
+    self.written += 1;
+    Ok(())
+  }
+
+  pub fn finish(self) {
+    if self.format == OutputFormat::Json {
+      match self.written {
+        0 => println!("[]"),
+        _ => println!("]"),
+      }
+    }
+  }
+}
+
+/// Prints a single value, pretty printed with `json` and on one line with `ndjson`
+pub fn print_value<T: Serialize>(format: OutputFormat, value: &T) -> anyhow::Result<()> {
+  match format {
+    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+    _ => println!("{}", serde_json::to_string(value)?),
+  }
+  Ok(())
+}
+
+/// Prints rows under a header. With `csv` every row is printed as soon as it's given, a `table`
+/// has to see every row to size its columns so it's printed by [RowWriter::finish]
+pub struct RowWriter {
+  format: OutputFormat,
+  headers: Vec<String>,
+  right_aligned: Vec<usize>,
+  rows: Vec<Vec<String>>,
+  written: usize,
+}
+
+impl RowWriter {
+  /// Creates a writer for the given columns, `right_aligned` are the indices of the columns
+  /// aligned to the right in a table, such as sizes
+  pub fn new(format: OutputFormat, headers: &[&str], right_aligned: &[usize]) -> Self {
+    Self {
+      format,
+      headers: headers.iter().map(|h| h.to_string()).collect(),
+      right_aligned: right_aligned.to_vec(),
+      rows: Vec::new(),
+      written: 0,
+    }
+  }
+
+  pub fn write(&mut self, row: Vec<String>) {
+    if self.format == OutputFormat::Csv {
+      if self.written == 0 {
+        println!("{}", csv_line(&self.headers));
+      }
+      println!("{}", csv_line(&row));
+    } else {
+      self.rows.push(row);
+    }
+
+    self.written += 1;
+  }
+
+  pub fn finish(self) {
+    match self.format {
+      OutputFormat::Csv if self.written == 0 => println!("{}", csv_line(&self.headers)),
+      OutputFormat::Table => print!("{}", render_table(&self.headers, &self.rows, &self.right_aligned)),
+      _ => {}
+    }
+  }
+}
+
+/// Joins fields into a CSV line, fields with a comma, quote or line break are quoted
+fn csv_line(fields: &[String]) -> String {
+  fields
+     .iter()
+     .map(|field| match field.contains([',', '"', '\n', '\r']) {
+       true => format!("\"{}\"", field.replace('"', "\"\"")),
+       false => field.to_string(),
+     })
+     .collect::<Vec<_>>()
+     .join(",")
+}
+
+/// Renders rows as columns padded to the widest value, headers are printed in upper case
+fn render_table(headers: &[String], rows: &[Vec<String>], right_aligned: &[usize]) -> String {
+  let headers = headers.iter().map(|h| h.to_uppercase()).collect::<Vec<_>>();
+
+  let mut widths = headers.iter().map(|h| h.chars().count()).collect::<Vec<_>>();
+  for row in rows {
+    for (index, value) in row.iter().enumerate().take(widths.len()) {
+      widths[index] = widths[index].max(value.chars().count());
+    }
+  }
+
+  let mut table = String::new();
+  for row in std::iter::once(&headers).chain(rows) {
+    let line = row
+       .iter()
+       .zip(&widths)
+       .enumerate()
+       .map(|(index, (value, width))| match right_aligned.contains(&index) {
+         true => format!("{:>width$}", value, width = width),
+         false => format!("{:<width$}", value, width = width),
+       })
+       .collect::<Vec<_>>()
+       .join("  ");
+
+    table.push_str(line.trim_end());
+    table.push('\n');
+  }
+
+  table
+}
+
+#[cfg(test)]
+mod output_tests {
+  use super::*;
+
+  #[test]
+  fn test_output_format_from_name() {
+    assert_eq!(OutputFormat::from_name("json"), Some(OutputFormat::Json));
+    assert_eq!(OutputFormat::from_name("NDJSON"), Some(OutputFormat::Ndjson));
+    assert_eq!(OutputFormat::from_name("text"), Some(OutputFormat::Text));
+    assert_eq!(OutputFormat::from_name("CSV"), Some(OutputFormat::Csv));
+    assert_eq!(OutputFormat::from_name("yaml"), None);
+  }
+
+  #[test]
+  fn test_csv_line() {
+    let fields = vec![String::from("a"), String::from("b,c"), String::from("say \"hi\"")];
+    assert_eq!(csv_line(&fields), "a,\"b,c\",\"say \"\"hi\"\"\"");
+  }
+
+  #[test]
+  fn test_render_table() {
+    let headers = vec![String::from("type"), String::from("size"), String::from("key")];
+    let rows = vec![
+      vec![String::from("DIR"), String::from("1024"), String::from("logs")],
+      vec![String::from("OBJ"), String::from("7"), String::from("readme.md")],
+    ];
+
+    assert_eq!(
+      render_table(&headers, &rows, &[1]),
+      "TYPE  SIZE  KEY\nDIR   1024  logs\nOBJ      7  readme.md\n"
+    );
+  }
+}
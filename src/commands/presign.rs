@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::commands::CommandOpts;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <PresignOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  match bkt.presign(opts).await {
+    Ok(url) => println!("{}", url),
+    Err(e) => {
+      eprintln!("{} {:?}", "error:".red(), e.to_string());
+      std::process::exit(1);
+    }
+  }
+
+  Ok(())
+}
+
+/// Request a presigned URL allows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PresignMethod {
+  Get,
+  Put,
+}
+
+#[derive(Clone, Debug)]
+pub struct PresignOpts {
+  pub verbose: bool,
+  pub delimiter: char,
+  pub path: String,
+  pub expires: Duration,
+  pub method: PresignMethod,
+  pub content_type: Option<String>,
+}
+
+impl CommandOpts for PresignOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let path = args.parse_prefix("PATH", false).unwrap();
+
+    let expires = match sub_matches.get_one::<String>("expires") {
+      Some(value) => parse_duration(value).unwrap_or_else(|| {
+        eprintln!("{} {} is not a valid duration (e.g., 900, 15m, 1h or 7d)", "error:".red(), value);
+        std::process::exit(1);
+      }),
+      None => Duration::from_secs(3600),
+    };
+
+    let method = match sub_matches.get_one::<String>("method").map(|m| m.to_uppercase()).as_deref() {
+      None | Some("GET") => PresignMethod::Get,
+      Some("PUT") => PresignMethod::Put,
+      Some(other) => {
+        eprintln!("{} {} is not a supported method, expected GET or PUT", "error:".red(), other);
+        std::process::exit(1);
+      }
+    };
+
+    let content_type = sub_matches.get_one::<String>("content-type").cloned();
+    if content_type.is_some() && method != PresignMethod::Put {
+      eprintln!("{} --content-type only applies to PUT", "error:".red());
+      std::process::exit(1);
+    }
+
+    Self {
+      verbose,
+      delimiter,
+      path,
+      expires,
+      method,
+      content_type,
+    }
+  }
+}
+
+/// Parses a duration in seconds with an optional unit suffix (s, m, h, d), e.g. `900`, `15m` or `7d`
+fn parse_duration(value: &str) -> Option<Duration> {
+  let value = value.trim();
+  let split_at = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+  let (number, unit) = value.split_at(split_at);
+  let number = number.parse::<u64>().ok()?;
+
+  let multiplier: u64 = match unit {
+    "" | "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" => 24 * 60 * 60,
+    _ => return None
+  };
+
+  match number.checked_mul(multiplier)? {
+    0 => None,
+    seconds => Some(Duration::from_secs(seconds)),
+  }
+}
+
+#[cfg(test)]
+mod presign_tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(parse_duration("900"), Some(Duration::from_secs(900)));
+    assert_eq!(parse_duration("15m"), Some(Duration::from_secs(900)));
+    assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+    assert_eq!(parse_duration("7d"), Some(Duration::from_secs(604800)));
+    assert_eq!(parse_duration("0"), None);
+    assert_eq!(parse_duration("1w"), None);
+    assert_eq!(parse_duration("h"), None);
+  }
+}
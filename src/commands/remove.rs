@@ -0,0 +1,56 @@
+use clap::ArgMatches;
+use colored::Colorize;
+use regex::Regex;
+
+use crate::commands::CommandOpts;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <RemoveOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.rm(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoveOpts {
+  pub verbose: bool,
+  pub recursive: bool,
+  pub dry_run: bool,
+  pub delimiter: char,
+  pub path: String,
+  pub exclude: Vec<Regex>,
+}
+
+impl CommandOpts for RemoveOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+    let recursive = sub_matches.get_flag("recursive");
+    let dry_run = sub_matches.get_flag("dry-run");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let path = args.parse_prefix("PATH", false).unwrap();
+
+    let exclude = args.parse_exclude();
+
+    Self {
+      verbose,
+      recursive,
+      dry_run,
+      delimiter,
+      path,
+      exclude,
+    }
+  }
+}
@@ -0,0 +1,44 @@
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::commands::CommandOpts;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <RemoveBucketOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.rb(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoveBucketOpts {
+  pub verbose: bool,
+  pub force: bool,
+  pub path: String,
+}
+
+impl CommandOpts for RemoveBucketOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+    let force = sub_matches.get_flag("force");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let path = args.parse_prefix("BUCKET", false).unwrap();
+
+    Self {
+      verbose,
+      force,
+      path,
+    }
+  }
+}
@@ -0,0 +1,53 @@
+use clap::ArgMatches;
+use colored::Colorize;
+use regex::Regex;
+
+use crate::commands::CommandOpts;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <StatOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.stat(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct StatOpts {
+  pub verbose: bool,
+  pub recursive: bool,
+  pub delimiter: char,
+  pub path: String,
+  pub exclude: Vec<Regex>,
+}
+
+impl CommandOpts for StatOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+    let recursive = sub_matches.get_flag("recursive");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let path = args.parse_prefix("PATH", false).unwrap();
+
+    let exclude = args.parse_exclude();
+
+    Self {
+      verbose,
+      recursive,
+      delimiter,
+      path,
+      exclude,
+    }
+  }
+}
@@ -0,0 +1,104 @@
+use clap::ArgMatches;
+use colored::Colorize;
+use regex::Regex;
+
+use crate::commands::CommandOpts;
+use crate::s3::bucket::multipart::{DEFAULT_CONCURRENCY, DEFAULT_PART_SIZE, MIN_PART_SIZE};
+use crate::s3::bucket::transfer::TransferOpts;
+use crate::s3::{ACL, ParsedS3Url};
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <SyncOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if let Err(e) = bkt.sync(opts).await {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  }
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncOpts {
+  pub verbose: bool,
+  pub show_progress: bool,
+  pub dry_run: bool,
+  pub delete: bool,
+  pub force: bool,
+  pub checksum: bool,
+  pub delimiter: char,
+  pub source: String,
+  pub destination: String,
+  pub exclude: Vec<Regex>,
+  pub part_size: u64,
+  pub concurrency: usize,
+  pub acl: Option<ACL>,
+}
+
+impl SyncOpts {
+  /// Settings applied to every transferred file
+  pub fn transfer(&self) -> TransferOpts {
+    TransferOpts {
+      part_size: self.part_size,
+      concurrency: self.concurrency,
+      acl: self.acl,
+    }
+  }
+}
+
+impl CommandOpts for SyncOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+    let show_progress = sub_matches.get_flag("progress");
+    let dry_run = sub_matches.get_flag("dry-run");
+    let delete = sub_matches.get_flag("delete");
+    let force = sub_matches.get_flag("force");
+    let checksum = sub_matches.get_flag("checksum");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let exclude = args.parse_exclude();
+
+    let part_size = args.parse_size("part-size", DEFAULT_PART_SIZE);
+    if part_size < MIN_PART_SIZE {
+      eprintln!("{} Part size must be at least 5MiB", "error:".red());
+      std::process::exit(1);
+    }
+
+    let concurrency = args.parse_count("concurrency", DEFAULT_CONCURRENCY);
+
+    let acl = args.parse_acl();
+
+    let source = sub_matches.get_one::<String>("SRC").cloned().unwrap_or_default();
+    let destination = sub_matches.get_one::<String>("DST").cloned().unwrap_or_default();
+
+    // Exactly one side of a sync is an s3 url, the other one is a local directory
+    if ParsedS3Url::is_s3url(&source) == ParsedS3Url::is_s3url(&destination) {
+      eprintln!("{} One of SRC or DST must be a local path and the other a valid s3 URL", "error:".red());
+      std::process::exit(1);
+    }
+
+    Self {
+      verbose,
+      show_progress,
+      dry_run,
+      delete,
+      force,
+      checksum,
+      delimiter,
+      source,
+      destination,
+      exclude,
+      part_size,
+      concurrency,
+      acl,
+    }
+  }
+}
@@ -0,0 +1,77 @@
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::commands::CommandOpts;
+
+/// Number of levels shown when `--depth` isn't given
+pub const DEFAULT_TREE_DEPTH: usize = 3;
+
+pub async fn run(sub_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+  let bkt = crate::commands::CmdArgs::from(sub_matches).get_bucket();
+  let opts = <TreeOpts as CommandOpts>::from(sub_matches);
+
+  if opts.verbose {
+    println!("{:?}", opts);
+  }
+
+  if opts.depth == Some(0) {
+    eprintln!("{} Depth must be at least 1", "error:".red());
+    std::process::exit(1);
+  }
+
+  let tree = bkt.tree(opts.clone()).await.unwrap_or_else(|e| {
+    eprintln!("{} {:?}", "error:".red(), e.to_string());
+    std::process::exit(1);
+  });
+
+  for line in tree.render(opts.delimiter, opts.human_readable) {
+    println!("{}", line);
+  }
+
+  // Like `tree`, the summary only counts what was shown
+  let (directories, objects) = tree.counts();
+  println!();
+  println!("{} directories, {} objects", directories, objects);
+
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeOpts {
+  pub verbose: bool,
+  pub human_readable: bool,
+  pub delimiter: char,
+  pub path: String,
+  /// Number of levels below the path that are shown, deeper objects only count towards the sizes.
+  /// `None` shows every level
+  pub depth: Option<usize>,
+  /// Number of shards of the keyspace listed at the same time
+  pub list_concurrency: usize,
+}
+
+impl CommandOpts for TreeOpts {
+  fn from(sub_matches: &ArgMatches) -> Self {
+    let verbose = sub_matches.get_flag("verbose");
+    let human_readable = sub_matches.get_flag("human-readable");
+
+    let args = crate::commands::CmdArgs::from(sub_matches);
+
+    let delimiter = args.parse_delimiter();
+
+    let path = args.parse_prefix("PATH", false).unwrap();
+
+    // Every entry shown is held until the listing ends, so the tree is kept shallow by default
+    let depth = Some(args.parse_depth().unwrap_or(DEFAULT_TREE_DEPTH));
+
+    let list_concurrency = args.parse_count("list-concurrency", 1);
+
+    Self {
+      verbose,
+      human_readable,
+      delimiter,
+      path,
+      depth,
+      list_concurrency,
+    }
+  }
+}
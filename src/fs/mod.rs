@@ -3,6 +3,7 @@ pub struct ClarifiedFile {
   path: std::path::PathBuf,
   sha256: String,
   length: usize,
+  modified: std::time::SystemTime,
 }
 
 impl ClarifiedFile {
@@ -11,20 +12,42 @@ impl ClarifiedFile {
       return Err(anyhow::anyhow!(format!("File does not exist: {}", &path)));
     }
 
-    let buffer = std::fs::read(&path);
-    if let Err(e) = buffer {
+    // The digest is computed by streaming the file, so large files are never loaded in memory
+    let sha256 = sha256::try_digest(std::path::Path::new(&path));
+    if let Err(e) = sha256 {
       return Err(anyhow::anyhow!(format!("An error occurred while reading file: {}", e.to_string())));
     }
-    let buffer = buffer.unwrap();
+    let sha256 = sha256.unwrap();
 
-    let sha256 = sha256::digest(&buffer);
+    let metadata = std::fs::metadata(&path)?;
 
     Ok(Self {
       path: std::path::PathBuf::from(&path),
       sha256,
-      length: buffer.len(),
+      length: metadata.len() as usize,
+      modified: metadata.modified()?,
     })
   }
+
+  /// Path of this file on local
+  pub fn path(&self) -> &std::path::Path {
+    self.path.as_path()
+  }
+
+  /// Hex encoded SHA-256 digest of the file contents
+  pub fn sha256(&self) -> &str {
+    &self.sha256
+  }
+
+  /// Size of the file in bytes
+  pub fn length(&self) -> usize {
+    self.length
+  }
+
+  /// Last modification time of the file
+  pub fn modified(&self) -> std::time::SystemTime {
+    self.modified
+  }
 }
 
 
@@ -37,51 +60,74 @@ mod clarified_file_tests {
     let file = ClarifiedFile::from_path("LICENSE");
     println!("{:?}", file);
   }
+
+  #[test]
+  fn test_md5_digest() {
+    let path = std::env::temp_dir().join(format!("s3cli-md5-{}", std::process::id()));
+    std::fs::write(&path, "hello").unwrap();
+    let digest = md5_digest(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(digest.unwrap(), "5d41402abc4b2a76b9719d911017c592");
+  }
 }
 
-/// Returns a list of files on local that matches the given pattern
-pub fn list_directory_content(
+/// Returns the hex encoded MD5 digest of a file, the file is streamed through the hasher
+pub fn md5_digest(path: &std::path::Path) -> anyhow::Result<String> {
+  use md5::{Digest, Md5};
+
+  let mut file = std::fs::File::open(path)?;
+  let mut hasher = Md5::new();
+  std::io::copy(&mut file, &mut hasher)?;
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns a hidden sibling path of the given file, used for writing contents before moving
+/// them in place with a rename
+pub fn temp_path_for(path: &std::path::Path) -> std::path::PathBuf {
+  let name = path.file_name()
+     .map(|n| n.to_string_lossy().to_string())
+     .unwrap_or_default();
+  path.with_file_name(format!(".{}.{}.s3cli-tmp", name, std::process::id()))
+}
+
+/// Returns the paths of the files under the given directory without reading their contents
+pub fn list_directory_files(
   path: &str,
   recursive: bool,
-) -> anyhow::Result<Vec<ClarifiedFile>> {
-
-  // 1. checking path exists
-  if false == std::path::Path::new(&path).exists() {
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+  if !std::path::Path::new(path).exists() {
     return Err(anyhow::anyhow!(format!("Path does not exist: {}", &path)));
   }
 
   let path = std::path::PathBuf::from(&path);
-
-  // 2. if path was a file return it
   if path.is_file() {
-    return ClarifiedFile::from_path(&path.as_path().to_str().unwrap())
-       .map(|f| vec![f]);
+    return Ok(vec![path]);
   }
 
-  // 3. if path was a directory, list all files in it
   let mut files = Vec::new();
-
   for entry in std::fs::read_dir(path)? {
-    let entry = entry?;
-    let path = entry.path();
+    let path = entry?.path();
     if path.is_file() {
-      files.push(ClarifiedFile::from_path(&path.as_path().to_str().unwrap())?);
-    }
-
-    // 4. if recursive, list all files in subdirectories
-    if recursive && path.is_dir() {
-      // just call this function again
-      let mut sub_files = list_directory_content(
-        &path.as_path().to_str().unwrap(),
-        recursive,
-      )?;
-      files.append(&mut sub_files);
+      files.push(path);
+    } else if recursive && path.is_dir() {
+      files.append(&mut list_directory_files(&path.to_string_lossy(), recursive)?);
     }
   }
 
   Ok(files)
 }
 
+/// Returns a list of files on local that matches the given pattern
+pub fn list_directory_content(
+  path: &str,
+  recursive: bool,
+) -> anyhow::Result<Vec<ClarifiedFile>> {
+  list_directory_files(path, recursive)?
+     .iter()
+     .map(|path| ClarifiedFile::from_path(&path.to_string_lossy()))
+     .collect()
+}
+
 #[cfg(test)]
 mod list_directory_content_tests {
   use super::*;
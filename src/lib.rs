@@ -27,6 +27,16 @@ pub fn read_til_regex(
   Ok(())
 }
 
+/// Asks a yes/no question on stdin, anything other than "y" is a no
+pub fn confirm(ask: &str) -> anyhow::Result<bool> {
+  print!("{} [y/N] ", ask);
+  std::io::stdout().flush().unwrap();
+
+  let mut input = String::new();
+  std::io::stdin().read_line(&mut input)?;
+  Ok(input.trim().to_lowercase() == "y")
+}
+
 // converts DateTime { seconds: 1678997191, subsecond_nanos: 341000000 } to timestampz (2023-02-13 12:59:51.341 UTC)
 pub fn utc_datetime(datetime: aws_sdk_s3::primitives::DateTime) -> String {
   let datetime = chrono::NaiveDateTime::from_timestamp_opt(
@@ -11,8 +11,28 @@ async fn main() -> anyhow::Result<()> {
     Some(("du", sub_matches)) => { s3cli::commands::du::run(sub_matches).await? }
     // Make Profile subcommand
     Some(("make-profile", sub_matches)) => { s3cli::commands::make_profile::run(sub_matches).await? }
+    // Cat subcommand
+    Some(("cat", sub_matches)) => { s3cli::commands::cat::run(sub_matches).await? }
     // Copy subcommand
     Some(("cp", sub_matches)) => { s3cli::commands::copy::run(sub_matches).await? }
+    // Move subcommand
+    Some(("mv", sub_matches)) => { s3cli::commands::mv::run(sub_matches).await? }
+    // Stat subcommand
+    Some(("stat", sub_matches)) => { s3cli::commands::stat::run(sub_matches).await? }
+    // Sync subcommand
+    Some(("sync", sub_matches)) => { s3cli::commands::sync::run(sub_matches).await? }
+    // ACL subcommand
+    Some(("acl", sub_matches)) => { s3cli::commands::acl::run(sub_matches).await? }
+    // Make Bucket subcommand
+    Some(("mb", sub_matches)) => { s3cli::commands::make_bucket::run(sub_matches).await? }
+    // Remove Bucket subcommand
+    Some(("rb", sub_matches)) => { s3cli::commands::remove_bucket::run(sub_matches).await? }
+    // Presign subcommand
+    Some(("presign", sub_matches)) => { s3cli::commands::presign::run(sub_matches).await? }
+    // Tree subcommand
+    Some(("tree", sub_matches)) => { s3cli::commands::tree::run(sub_matches).await? }
+    // Remove subcommand
+    Some(("rm", sub_matches)) => { s3cli::commands::remove::run(sub_matches).await? }
     // If all subcommands are defined above, anything else is unreachable!()
     _ => unreachable!(),
   }
@@ -34,6 +54,7 @@ fn cli() -> Command {
      .arg_required_else_help(true)
      .allow_external_subcommands(true)
      .author("Shahrad Elahi <https://github.com/shahradelahi>")
+     .arg(arg!(--output <FORMAT> "output format of ls, du and common-prefix (text, json, ndjson, csv, table)").global(true))
      // List subcommand
      .subcommand(
        Command::new("ls")
@@ -46,6 +67,8 @@ fn cli() -> Command {
             arg!(--delimiter <DELIMITER> "delimiter to split the path"),
             arg!(-H --"human-readable" "print sizes in human readable format (e.g., 1K 234M 2G)"),
             arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+            arg!(--"dir-sizes" "show the size of directories, this lists every object under the path"),
+            arg!(--"list-concurrency" <N> "number of prefixes of the path listed at the same time with -r or --dir-sizes"),
             arg!(--progress "show progress bar"),
             arg!(--verbose "show verbose output"),
           ])
@@ -59,6 +82,7 @@ fn cli() -> Command {
           .arg_required_else_help(true)
           .args([
             arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(-d --depth <N> "also list the prefixes up to N levels below the path"),
             arg!(--progress "show progress bar"),
           ])
      )
@@ -82,24 +106,199 @@ fn cli() -> Command {
           .args([
             arg!(--delimiter <DELIMITER> "delimiter to split the path"),
             arg!(--total "show total size of the path"),
+            arg!(-d --depth <N> "also show the total of every prefix up to N levels below the path, like du -d"),
             arg!(-H --"human-readable" "print sizes in human readable format (e.g., 1K 234M 2G)"),
+            arg!(--"list-concurrency" <N> "number of prefixes of the path listed at the same time"),
             arg!(--progress "show progress bar"),
             arg!(--verbose "show verbose output"),
           ])
      )
+     // Cat subcommand
+     .subcommand(
+       Command::new("cat")
+          .about("Stream an object to stdout")
+          .args(&connection_args)
+          .args([
+            arg!(<PATH> "Path of the object to stream").required(true),
+            arg!(--range <RANGE> "only stream the given inclusive byte range (e.g., 0-1023 or 1024-)"),
+            arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
      // Copy subcommand
      .subcommand(
        Command::new("cp")
           .about("Copy a file or directory")
           .args(&connection_args)
           .args([
-            arg!(<FROM> "source path").required(true),
+            arg!(<FROM> "source path, or - to read from stdin").required(true),
             arg!(<TO> "destination path").required(true),
             arg!(-r --recursive "recursively copy all files including subdirectories under the given path"),
             arg!(--delimiter <DELIMITER> "delimiter to split the path").required_if_eq("recursive", "true"),
             arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+            arg!(--"part-size" <SIZE> "size of each part for multipart uploads and ranged downloads (e.g., 8M, 64MiB, 1G)"),
+            arg!(--concurrency <N> "number of parts uploaded or ranges downloaded at the same time"),
+            arg!(--acl <ACL> "canned ACL applied to uploaded objects (e.g., private, public-read)"),
+            arg!(--checksum "compare contents by checksum instead of size and modification time, unchanged files are skipped. With - the SHA-256 of stdin is stored once the upload completed"),
+            arg!(--progress "show progress bar"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // Move subcommand
+     .subcommand(
+       Command::new("mv")
+          .about("Move a file or directory, sources are removed once their copy is verified")
+          .args(&connection_args)
+          .args([
+            arg!(<FROM> "source path").required(true),
+            arg!(<TO> "destination path").required(true),
+            arg!(-r --recursive "recursively move all files including subdirectories under the given path"),
+            arg!(--delimiter <DELIMITER> "delimiter to split the path").required_if_eq("recursive", "true"),
+            arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+            arg!(--"part-size" <SIZE> "size of each part for multipart uploads and ranged downloads (e.g., 8M, 64MiB, 1G)"),
+            arg!(--concurrency <N> "number of parts uploaded or ranges downloaded at the same time"),
+            arg!(--acl <ACL> "canned ACL applied to uploaded objects (e.g., private, public-read)"),
+            arg!(--checksum "compare contents by checksum, unchanged files are not transferred again before the source is removed"),
+            arg!(--progress "show progress bar"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // Stat subcommand
+     .subcommand(
+       Command::new("stat")
+          .about("Show the metadata of an object")
+          .visible_alias("head")
+          .args(&connection_args)
+          .arg(arg!(<PATH> "Path of the object").required(true))
+          .arg_required_else_help(true)
+          .args([
+            arg!(-r --recursive "show the metadata of every object under the given path"),
+            arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // Tree subcommand
+     .subcommand(
+       Command::new("tree")
+          .about("Show the directories and objects under a path as a tree with their sizes")
+          .args(&connection_args)
+          .arg(arg!(<PATH> "Path to show").required(true))
+          .arg_required_else_help(true)
+          .args([
+            arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(-d --depth <N> "show at most N levels below the path, 3 by default. Everything shown is kept in memory until the listing ends"),
+            arg!(-H --"human-readable" "print sizes in human readable format (e.g., 1K 234M 2G)"),
+            arg!(--"list-concurrency" <N> "number of prefixes of the path listed at the same time"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // Sync subcommand
+     .subcommand(
+       Command::new("sync")
+          .about("Sync a directory with an S3 prefix, only new or changed files are transferred")
+          .args(&connection_args)
+          .args([
+            arg!(<SRC> "source path").required(true),
+            arg!(<DST> "destination path").required(true),
+            arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+            arg!(--"dry-run" "show what would be transferred without transferring it"),
+            arg!(--delete "delete destination files that don't exist in the source"),
+            arg!(--force "don't ask for confirmation before deleting"),
+            arg!(--"part-size" <SIZE> "size of each part for multipart uploads and ranged downloads (e.g., 8M, 64MiB, 1G)"),
+            arg!(--concurrency <N> "number of parts uploaded or ranges downloaded at the same time"),
+            arg!(--acl <ACL> "canned ACL applied to uploaded objects (e.g., private, public-read)"),
+            arg!(--checksum "compare contents by checksum instead of size and modification time, unchanged files are skipped"),
             arg!(--progress "show progress bar"),
             arg!(--verbose "show verbose output"),
           ])
      )
+     // Presign subcommand
+     .subcommand(
+       Command::new("presign")
+          .about("Create a presigned URL to download or upload an object without credentials")
+          .args(&connection_args)
+          .arg(arg!(<PATH> "Path of the object").required(true))
+          .arg_required_else_help(true)
+          .args([
+            arg!(--expires <DURATION> "time until the URL expires, at most 7d (e.g., 900, 15m, 1h) [default: 1h]"),
+            arg!(--method <METHOD> "request the URL allows, GET or PUT [default: GET]"),
+            arg!(--"content-type" <TYPE> "content type the upload must be sent with, only for PUT"),
+            arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // Remove subcommand
+     .subcommand(
+       Command::new("rm")
+          .about("Remove an object or every object under a prefix")
+          .args(&connection_args)
+          .arg(arg!(<PATH> "Path to remove").required(true))
+          .arg_required_else_help(true)
+          .args([
+            arg!(-r --recursive "recursively remove all objects under the given path"),
+            arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+            arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+            arg!(--"dry-run" "show what would be removed without removing it"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // ACL subcommand
+     .subcommand(
+       Command::new("acl")
+          .about("Show or change the ACL of objects")
+          .subcommand_required(true)
+          .arg_required_else_help(true)
+          .subcommand(
+            Command::new("get")
+               .about("Show the owner and grants of an object")
+               .args(&connection_args)
+               .arg(arg!(<PATH> "Path of the object").required(true))
+               .args([
+                 arg!(-r --recursive "show the ACL of every object under the given path"),
+                 arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+                 arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+                 arg!(--verbose "show verbose output"),
+               ])
+          )
+          .subcommand(
+            Command::new("set")
+               .about("Apply a canned ACL to an object")
+               .args(&connection_args)
+               .arg(arg!(<PATH> "Path of the object").required(true))
+               .args([
+                 arg!(--acl <ACL> "canned ACL to apply (e.g., private, public-read)").required(true),
+                 arg!(-r --recursive "apply the ACL to every object under the given path"),
+                 arg!(--delimiter <DELIMITER> "delimiter to split the path"),
+                 arg!(--exclude <PATTERN> "exclude contents matching the pattern"),
+                 arg!(--"dry-run" "show what would be changed without changing it"),
+                 arg!(--verbose "show verbose output"),
+               ])
+          )
+     )
+     // Make Bucket subcommand
+     .subcommand(
+       Command::new("mb")
+          .about("Make a bucket")
+          .args(&connection_args)
+          .arg(arg!(<BUCKET> "Bucket to make (e.g., s3://bucket)").required(true))
+          .arg_required_else_help(true)
+          .args([
+            arg!(--acl <ACL> "canned ACL of the bucket (private, public-read, public-read-write, authenticated-read)"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
+     // Remove Bucket subcommand
+     .subcommand(
+       Command::new("rb")
+          .about("Remove a bucket")
+          .args(&connection_args)
+          .arg(arg!(<BUCKET> "Bucket to remove (e.g., s3://bucket)").required(true))
+          .arg_required_else_help(true)
+          .args([
+            arg!(--force "delete every object in the bucket before removing it"),
+            arg!(--verbose "show verbose output"),
+          ])
+     )
 }
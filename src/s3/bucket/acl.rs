@@ -0,0 +1,120 @@
+use aws_sdk_s3::types::Grantee;
+
+use crate::commands::acl::AclOpts;
+use crate::s3::bucket::Bucket;
+use crate::s3::bucket::transfer::{object_acl, relative_key};
+use crate::s3::ParsedS3Url;
+
+impl Bucket {
+  /// Prints the owner and grants of an object, or of every object under a prefix when recursive
+  pub async fn acl_get(&self, opts: AclOpts) -> anyhow::Result<()> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+
+    for key in self.acl_keys(&opts).await? {
+      let output = self.client
+         .get_object_acl()
+         .bucket(&parsed.bucket_name)
+         .key(&key)
+         .send()
+         .await?;
+
+      println!("s3://{}/{}", parsed.bucket_name, key);
+
+      if let Some(owner) = output.owner() {
+        let name = owner.display_name().or(owner.id()).unwrap_or_default();
+        println!("  owner: {}", name);
+      }
+
+      for grant in output.grants().unwrap_or_default() {
+        let permission = grant.permission().map(|p| p.as_str()).unwrap_or_default();
+        let grantee = grant.grantee().map(describe_grantee).unwrap_or_default();
+        println!("  {}: {}", permission, grantee);
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Applies a canned ACL to an object, or to every object under a prefix when recursive
+  pub async fn acl_set(&self, opts: AclOpts) -> anyhow::Result<()> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let acl = match opts.acl {
+      Some(acl) => acl,
+      None => return Err(anyhow::anyhow!("An ACL is required, use --acl to give one")),
+    };
+
+    for key in self.acl_keys(&opts).await? {
+      if opts.dry_run {
+        println!("(dryrun) acl: {} s3://{}/{}", acl.to_string(), parsed.bucket_name, key);
+        continue;
+      }
+
+      self.client
+         .put_object_acl()
+         .bucket(&parsed.bucket_name)
+         .key(&key)
+         .acl(object_acl(acl))
+         .send()
+         .await?;
+
+      println!("acl: {} s3://{}/{}", acl.to_string(), parsed.bucket_name, key);
+    }
+
+    Ok(())
+  }
+
+  /// Keys the ACL command applies to, the object itself or every object under the prefix
+  async fn acl_keys(&self, opts: &AclOpts) -> anyhow::Result<Vec<String>> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let prefix = parsed.segments.join(opts.delimiter.to_string().as_str());
+
+    if !opts.recursive {
+      if prefix.is_empty() {
+        return Err(anyhow::anyhow!("An object key is required, use --recursive to apply to a whole prefix"));
+      }
+      return Ok(vec![prefix]);
+    }
+
+    let keys = self.list_objects(&opts.path, &opts.delimiter)
+       .await?
+       .into_iter()
+       .filter_map(|object| object.key)
+       .filter(|key| relative_key(key, &prefix, opts.delimiter, &opts.exclude).is_some())
+       .collect();
+
+    Ok(keys)
+  }
+}
+
+/// Describes who a grant applies to, groups are named by their URI
+fn describe_grantee(grantee: &Grantee) -> String {
+  if let Some(uri) = grantee.uri() {
+    return uri.to_string();
+  }
+
+  match (grantee.display_name(), grantee.id(), grantee.email_address()) {
+    (Some(name), Some(id), _) => format!("{} ({})", name, id),
+    (None, Some(id), _) => id.to_string(),
+    (_, _, Some(email)) => email.to_string(),
+    (Some(name), None, None) => name.to_string(),
+    (None, None, None) => String::from("unknown"),
+  }
+}
+
+#[cfg(test)]
+mod acl_tests {
+  use super::*;
+
+  #[test]
+  fn test_describe_grantee() {
+    let group = Grantee::builder()
+       .uri("http://acs.amazonaws.com/groups/global/AllUsers")
+       .build();
+    assert_eq!(describe_grantee(&group), "http://acs.amazonaws.com/groups/global/AllUsers");
+
+    let user = Grantee::builder().display_name("owner").id("abc123").build();
+    assert_eq!(describe_grantee(&user), "owner (abc123)");
+
+    assert_eq!(describe_grantee(&Grantee::builder().build()), "unknown");
+  }
+}
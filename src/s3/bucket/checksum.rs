@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::Client;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+
+use crate::fs::md5_digest;
+use crate::s3::bucket::Bucket;
+
+/// User metadata key holding the SHA-256 of the contents, it's set on every upload
+pub const SHA256_METADATA_KEY: &str = "sha256";
+
+/// Checks whether an object has the same contents as a local file. The SHA-256 stored in the
+/// object metadata is compared when there is one, otherwise the ETag is compared with the MD5
+/// of the file as long as it's a plain MD5 (multipart ETags are not). A missing object or one
+/// that can't be compared is reported as different
+pub async fn matches_checksum(
+  client: Client,
+  bucket: String,
+  key: String,
+  path: PathBuf,
+  sha256: String,
+) -> bool {
+  let head = match client.head_object().bucket(bucket).key(key).send().await {
+    Ok(head) => head,
+    Err(_) => return false,
+  };
+
+  if let Some(remote) = head.metadata().and_then(|m| m.get(SHA256_METADATA_KEY)) {
+    return remote.eq_ignore_ascii_case(&sha256);
+  }
+
+  match head.e_tag().map(|t| t.trim_matches('"')) {
+    Some(e_tag) if is_plain_md5(e_tag) => match md5_digest(&path) {
+      Ok(md5) => md5.eq_ignore_ascii_case(e_tag),
+      Err(_) => false,
+    },
+    _ => false,
+  }
+}
+
+impl Bucket {
+  /// Confirms an uploaded object has the size of the local file and, when stored, its SHA-256
+  pub(crate) async fn verify_upload(&self, bucket: &str, key: &str, length: u64, sha256: &str) -> anyhow::Result<()> {
+    let head = self.client.head_object().bucket(bucket).key(key).send().await?;
+
+    if head.content_length() as u64 != length {
+      return Err(anyhow::anyhow!("Size of s3://{}/{} doesn't match the uploaded file", bucket, key));
+    }
+
+    if let Some(remote) = stored_sha256(&head) {
+      if !remote.eq_ignore_ascii_case(sha256) {
+        return Err(anyhow::anyhow!("Checksum of s3://{}/{} doesn't match the uploaded file", bucket, key));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Confirms a downloaded file has the size of the object, and its SHA-256 or MD5 when the
+  /// object has one to compare with
+  pub(crate) async fn verify_download(&self, bucket: &str, key: &str, path: &Path) -> anyhow::Result<()> {
+    let head = self.client.head_object().bucket(bucket).key(key).send().await?;
+
+    if tokio::fs::metadata(path).await?.len() != head.content_length() as u64 {
+      return Err(anyhow::anyhow!("Size of {} doesn't match s3://{}/{}", path.display(), bucket, key));
+    }
+
+    let matches = match (stored_sha256(&head), plain_e_tag(&head)) {
+      (Some(remote), _) => sha256::try_digest(path)?.eq_ignore_ascii_case(remote),
+      (None, Some(e_tag)) => md5_digest(path)?.eq_ignore_ascii_case(e_tag),
+      (None, None) => true,
+    };
+
+    if !matches {
+      return Err(anyhow::anyhow!("Checksum of {} doesn't match s3://{}/{}", path.display(), bucket, key));
+    }
+
+    Ok(())
+  }
+
+  /// Confirms a copied object has the size of its source, and the same ETag or SHA-256 when
+  /// both sides have one to compare. Objects copied in parts get a new multipart ETag, so
+  /// only plain ETags are compared
+  pub(crate) async fn verify_copy(
+    &self,
+    source_bucket: &str,
+    source_key: &str,
+    bucket: &str,
+    key: &str,
+  ) -> anyhow::Result<()> {
+    let source = self.client.head_object().bucket(source_bucket).key(source_key).send().await?;
+    let copied = self.client.head_object().bucket(bucket).key(key).send().await?;
+
+    if source.content_length() != copied.content_length() {
+      return Err(anyhow::anyhow!(
+        "Size of s3://{}/{} doesn't match s3://{}/{}",
+        bucket, key, source_bucket, source_key
+      ));
+    }
+
+    let matches = match (plain_e_tag(&source), plain_e_tag(&copied)) {
+      (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+      _ => match (stored_sha256(&source), stored_sha256(&copied)) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => true,
+      },
+    };
+
+    if !matches {
+      return Err(anyhow::anyhow!(
+        "Checksum of s3://{}/{} doesn't match s3://{}/{}",
+        bucket, key, source_bucket, source_key
+      ));
+    }
+
+    Ok(())
+  }
+}
+
+fn stored_sha256(head: &HeadObjectOutput) -> Option<&str> {
+  head.metadata()
+     .and_then(|m| m.get(SHA256_METADATA_KEY))
+     .map(|s| s.as_str())
+}
+
+fn plain_e_tag(head: &HeadObjectOutput) -> Option<&str> {
+  head.e_tag()
+     .map(|t| t.trim_matches('"'))
+     .filter(|t| is_plain_md5(t))
+}
+
+/// ETags of single part uploads are the MD5 of the contents, multipart ones end with `-<parts>`
+fn is_plain_md5(e_tag: &str) -> bool {
+  e_tag.len() == 32 && e_tag.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod checksum_tests {
+  use super::*;
+
+  #[test]
+  fn test_is_plain_md5() {
+    assert!(is_plain_md5("5d41402abc4b2a76b9719d911017c592"));
+    assert!(!is_plain_md5("5d41402abc4b2a76b9719d911017c592-3"));
+    assert!(!is_plain_md5(""));
+  }
+}
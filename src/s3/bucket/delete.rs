@@ -0,0 +1,73 @@
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use colored::Colorize;
+
+use crate::s3::bucket::Bucket;
+
+/// Maximum number of keys accepted by a single DeleteObjects request
+pub const DELETE_BATCH_SIZE: usize = 1000;
+
+/// A key the server refused to delete
+#[derive(Debug)]
+pub struct DeleteFailure {
+  pub key: String,
+  pub code: String,
+  pub message: String,
+}
+
+impl Bucket {
+  /// Deletes a single object
+  pub async fn delete_object(&self, bucket: &str, key: &str) -> anyhow::Result<()> {
+    self.client
+       .delete_object()
+       .bucket(bucket)
+       .key(key)
+       .send()
+       .await?;
+
+    Ok(())
+  }
+
+  /// Deletes the given keys with DeleteObjects in batches of 1000, returns the keys that failed
+  pub async fn delete_objects(&self, bucket: &str, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+    let mut failures: Vec<DeleteFailure> = Vec::new();
+
+    for batch in keys.chunks(DELETE_BATCH_SIZE) {
+      let objects = batch
+         .iter()
+         .map(|key| ObjectIdentifier::builder().key(key).build())
+         .collect::<Vec<_>>();
+
+      // Quiet mode only reports the keys that failed
+      let response = self.client
+         .delete_objects()
+         .bucket(bucket)
+         .delete(Delete::builder().set_objects(Some(objects)).quiet(true).build())
+         .send()
+         .await?;
+
+      for error in response.errors().unwrap_or_default() {
+        failures.push(DeleteFailure {
+          key: error.key().unwrap_or_default().to_string(),
+          code: error.code().unwrap_or_default().to_string(),
+          message: error.message().unwrap_or_default().to_string(),
+        });
+      }
+    }
+
+    Ok(failures)
+  }
+}
+
+/// Prints every key that couldn't be deleted, returns an error when there is any
+pub fn report_delete_failures(bucket: &str, failures: &[DeleteFailure], total: usize) -> anyhow::Result<()> {
+  if failures.is_empty() {
+    return Ok(());
+  }
+
+  eprintln!("{} {} of {} objects could not be deleted:", "error:".red(), failures.len(), total);
+  for failure in failures {
+    eprintln!("  s3://{}/{} ({}: {})", bucket, failure.key, failure.code, failure.message);
+  }
+
+  Err(anyhow::anyhow!("{} objects could not be deleted", failures.len()))
+}
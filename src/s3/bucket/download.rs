@@ -0,0 +1,117 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::Client;
+use indicatif::ProgressBar;
+use tokio::io::AsyncSeekExt;
+use tokio::task::JoinSet;
+
+use crate::s3::bucket::Bucket;
+use crate::s3::bucket::multipart::part_ranges;
+
+impl Bucket {
+  /// Downloads an object with concurrent ranged GetObject requests, each range is written at its
+  /// offset in the given file which is preallocated to the size of the object. Every request is
+  /// bound to the ETag of the object, so a change on the server in the middle fails the download
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) async fn download_ranged(
+    &self,
+    bucket: &str,
+    key: &str,
+    size: u64,
+    path: &Path,
+    part_size: u64,
+    concurrency: usize,
+    pb: &ProgressBar,
+  ) -> anyhow::Result<()> {
+    let head = self.client
+       .head_object()
+       .bucket(bucket)
+       .key(key)
+       .send()
+       .await?;
+
+    if head.content_length() as u64 != size {
+      return Err(anyhow::anyhow!("Size of {} has changed since it was listed", key));
+    }
+
+    let file = tokio::fs::File::create(path).await?;
+    file.set_len(size).await?;
+    drop(file);
+
+    let e_tag = head.e_tag().map(|t| t.to_string());
+    let mut tasks: JoinSet<anyhow::Result<u64>> = JoinSet::new();
+
+    for (_, offset, length) in part_ranges(size, part_size) {
+      if tasks.len() >= concurrency.max(1) {
+        if let Some(joined) = tasks.join_next().await {
+          let received = joined??;
+          if !pb.is_finished() {
+            pb.inc(received);
+          }
+        }
+      }
+
+      let range = RangeDownload {
+        client: self.client.clone(),
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        e_tag: e_tag.clone(),
+        path: path.to_path_buf(),
+        offset,
+        length,
+      };
+      tasks.spawn(async move { range.fetch().await });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+      let received = joined??;
+      if !pb.is_finished() {
+        pb.inc(received);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// A single byte range of an object, owned so it can be fetched from a spawned task
+struct RangeDownload {
+  client: Client,
+  bucket: String,
+  key: String,
+  e_tag: Option<String>,
+  path: PathBuf,
+  offset: u64,
+  length: u64,
+}
+
+impl RangeDownload {
+  async fn fetch(self) -> anyhow::Result<u64> {
+    let response = self.client
+       .get_object()
+       .bucket(&self.bucket)
+       .key(&self.key)
+       .range(format!("bytes={}-{}", self.offset, self.offset + self.length - 1))
+       .set_if_match(self.e_tag.clone())
+       .send()
+       .await?;
+
+    let mut file = tokio::fs::OpenOptions::new().write(true).open(&self.path).await?;
+    file.seek(SeekFrom::Start(self.offset)).await?;
+
+    let mut body = response.body.into_async_read();
+    let written = tokio::io::copy(&mut body, &mut file).await?;
+
+    if written != self.length {
+      return Err(anyhow::anyhow!(
+        "Received {} of {} bytes at offset {} of {}",
+        written, self.length, self.offset, self.key
+      ));
+    }
+
+    file.sync_data().await?;
+
+    Ok(written)
+  }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Return a absolute path to the directory keeping journals of in-progress uploads
+pub fn journal_directory() -> anyhow::Result<PathBuf> {
+  let user_home = std::env::var("HOME")?;
+  Ok(PathBuf::from(format!("{}/.s3cli/uploads", user_home)))
+}
+
+/// State of an in-progress multipart upload, persisted to disk so re-running the same upload
+/// continues where it stopped.
+///
+/// The journal is a plain text file, every completed part is appended as its own line:
+///
+/// ```text
+/// upload_id <upload id>
+/// part_size <bytes>
+/// part <part number> <etag>
+/// ```
+#[derive(Debug)]
+pub struct UploadJournal {
+  path: Option<PathBuf>,
+  pub upload_id: String,
+  pub part_size: u64,
+  pub parts: BTreeMap<i32, String>,
+}
+
+impl UploadJournal {
+  /// Returns the journal path of an upload. The journal is bound to the destination and to the
+  /// size and modification time of the local file, so a changed file never resumes stale parts.
+  /// The name starts with a digest of the destination alone, see [stale_journals]
+  pub fn path_for(endpoint: &str, bucket: &str, key: &str, local: &Path) -> anyhow::Result<PathBuf> {
+    let metadata = std::fs::metadata(local)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+    let absolute = std::fs::canonicalize(local)?;
+
+    let identity = format!(
+      "{}\n{}\n{}\n{}\n{}\n{}",
+      endpoint,
+      bucket,
+      key,
+      absolute.display(),
+      metadata.len(),
+      modified.as_nanos(),
+    );
+
+    let destination = sha256::digest(format!("{}\n{}\n{}", endpoint, bucket, key));
+
+    Ok(journal_directory()?.join(format!("{}-{}.journal", destination, sha256::digest(identity))))
+  }
+
+  /// Loads a journal, returns `None` when there is none or it can't be understood
+  pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+
+    Ok(parse_journal(&contents).map(|(upload_id, part_size, parts)| Self {
+      path: Some(path.to_path_buf()),
+      upload_id,
+      part_size,
+      parts,
+    }))
+  }
+
+  /// Starts a new journal for the given upload
+  pub fn create(path: &Path, upload_id: &str, part_size: u64) -> anyhow::Result<Self> {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(format!("upload_id {}\npart_size {}\n", upload_id, part_size).as_bytes())?;
+    file.sync_data()?;
+
+    Ok(Self {
+      path: Some(path.to_path_buf()),
+      upload_id: upload_id.to_string(),
+      part_size,
+      parts: BTreeMap::new(),
+    })
+  }
+
+  /// A journal that is never written to disk, used when there is no place to keep it
+  pub fn in_memory(upload_id: &str, part_size: u64) -> Self {
+    Self {
+      path: None,
+      upload_id: upload_id.to_string(),
+      part_size,
+      parts: BTreeMap::new(),
+    }
+  }
+
+  /// Whether this journal is kept on disk, so the upload can be resumed later
+  pub fn is_persisted(&self) -> bool {
+    self.path.is_some()
+  }
+
+  /// Records a completed part, it's flushed to disk before returning
+  pub fn record_part(&mut self, part_number: i32, e_tag: &str) -> anyhow::Result<()> {
+    if let Some(path) = &self.path {
+      let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+      file.write_all(format!("part {} {}\n", part_number, e_tag).as_bytes())?;
+      file.sync_data()?;
+    }
+
+    self.parts.insert(part_number, e_tag.to_string());
+    Ok(())
+  }
+
+  /// Removes the journal from disk
+  pub fn remove(self) {
+    if let Some(path) = &self.path {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+/// Returns the other journals of the same destination as the given journal path. They belong to
+/// uploads of an earlier version of the file, which can never be resumed
+pub fn stale_journals(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+  let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+  let destination = match name.split_once('-') {
+    Some((destination, _)) => format!("{}-", destination),
+    None => return Ok(Vec::new()),
+  };
+
+  let directory = match path.parent() {
+    Some(directory) if directory.is_dir() => directory,
+    _ => return Ok(Vec::new()),
+  };
+
+  let mut stale = Vec::new();
+  for entry in std::fs::read_dir(directory)? {
+    let entry = entry?.path();
+    let entry_name = entry.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if entry_name != name && entry_name.starts_with(&destination) && entry_name.ends_with(".journal") {
+      stale.push(entry);
+    }
+  }
+
+  stale.sort();
+  Ok(stale)
+}
+
+/// Parses the journal contents into (upload id, part size, parts). A line cut short by a crash
+/// is ignored, that part is simply uploaded again.
+fn parse_journal(contents: &str) -> Option<(String, u64, BTreeMap<i32, String>)> {
+  let mut upload_id: Option<String> = None;
+  let mut part_size: Option<u64> = None;
+  let mut parts: BTreeMap<i32, String> = BTreeMap::new();
+
+  for line in contents.split_inclusive('\n') {
+    if !line.ends_with('\n') {
+      break;
+    }
+
+    let mut fields = line.trim_end().splitn(3, ' ');
+    match (fields.next(), fields.next(), fields.next()) {
+      (Some("upload_id"), Some(id), None) => upload_id = Some(id.to_string()),
+      (Some("part_size"), Some(size), None) => part_size = size.parse().ok(),
+      (Some("part"), Some(number), Some(e_tag)) => {
+        if let Ok(number) = number.parse::<i32>() {
+          parts.insert(number, e_tag.to_string());
+        }
+      }
+      _ => return None,
+    }
+  }
+
+  Some((upload_id?, part_size?, parts))
+}
+
+#[cfg(test)]
+mod journal_tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_journal() {
+    let contents = "upload_id 2~abc\npart_size 8388608\npart 1 \"etag-1\"\npart 3 \"etag-3\"\npart 2 \"et";
+    let (upload_id, part_size, parts) = parse_journal(contents).unwrap();
+    assert_eq!(upload_id, "2~abc");
+    assert_eq!(part_size, 8388608);
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts.get(&3).unwrap(), "\"etag-3\"");
+
+    assert!(parse_journal("part_size 8388608\n").is_none());
+    assert!(parse_journal("garbage\n").is_none());
+  }
+
+  #[test]
+  fn test_stale_journals() {
+    let directory = std::env::temp_dir().join(format!("s3cli-journals-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    for name in ["aaa-1.journal", "aaa-2.journal", "bbb-1.journal"] {
+      std::fs::write(directory.join(name), "").unwrap();
+    }
+
+    let stale = stale_journals(&directory.join("aaa-1.journal"));
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(stale.unwrap(), vec![directory.join("aaa-2.journal")]);
+  }
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::pin::Pin;
+
+use aws_sdk_s3::Client;
+use aws_sdk_s3::types::Object;
+use tokio::sync::mpsc::{self, Sender};
+use tokio::task::JoinSet;
+use tokio_stream::{Stream, StreamExt};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::s3::bucket::{Bucket, get_list_object_request, list_prefix, list_request};
+use crate::s3::content::{S3Directory, S3File};
+use crate::s3::ParsedS3Url;
+
+/// Pages of a listing, the next page is only requested once the previous one was consumed
+pub type ObjectPages = Pin<Box<dyn Stream<Item=anyhow::Result<Vec<Object>>> + Send>>;
+
+/// How many levels below the listed path are searched for enough shards to list concurrently
+const MAX_SHARD_LEVELS: usize = 3;
+
+impl Bucket {
+  /// Streams every object under the given path page by page, following the continuation tokens
+  pub fn object_pages(&self, url: &String, delimiter: &char) -> anyhow::Result<ObjectPages> {
+    let pages = get_list_object_request(&self.client, url, delimiter)?
+       .into_paginator()
+       .send()
+       .map(|page| match page {
+         Ok(page) => Ok(page.contents().unwrap_or_default().to_vec()),
+         Err(e) => Err(anyhow::Error::from(e)),
+       });
+
+    Ok(Box::pin(pages))
+  }
+
+  /// Like [Bucket::object_pages], but the keyspace is split into shards by the common prefixes
+  /// below the path and up to `concurrency` shards are listed at the same time. Pages of
+  /// different shards are interleaved, so the objects are no longer in key order
+  pub fn sharded_object_pages(&self, url: &String, delimiter: &char, concurrency: usize) -> anyhow::Result<ObjectPages> {
+    if concurrency <= 1 {
+      return self.object_pages(url, delimiter);
+    }
+
+    let parsed = ParsedS3Url::parse_from(url, delimiter)?;
+    let client = self.client.clone();
+    let delimiter = *delimiter;
+
+    // A few pages per shard are buffered, the shards wait for the consumer past that
+    let (sender, receiver) = mpsc::channel(concurrency * 2);
+
+    tokio::spawn(async move {
+      if let Err(e) = list_shards(client, parsed, delimiter, concurrency, sender.clone()).await {
+        let _ = sender.send(Err(e)).await;
+      }
+    });
+
+    Ok(Box::pin(ReceiverStream::new(receiver)))
+  }
+
+  /// Streams a single level of the given path, deeper objects are left to the server to group
+  /// into common prefixes so the subtree under the path is never listed
+  pub fn level_pages(&self, url: &String, delimiter: &char) -> anyhow::Result<LevelPages> {
+    let prefix = ParsedS3Url::parse_from(url, delimiter)?.segments.join(delimiter.to_string().as_str());
+    let delimiter = *delimiter;
+
+    let pages = get_list_object_request(&self.client, url, &delimiter)?
+       .delimiter(delimiter.to_string())
+       .into_paginator()
+       .send()
+       .map(move |page| match page {
+         Ok(page) => Ok(LevelPage {
+           objects: page.contents().unwrap_or_default().to_vec(),
+           directories: page
+              .common_prefixes()
+              .unwrap_or_default()
+              .iter()
+              .map(|p| directory_name(&prefix, p.prefix().unwrap_or_default(), delimiter))
+              .collect(),
+         }),
+         Err(e) => Err(anyhow::Error::from(e)),
+       });
+
+    Ok(Box::pin(pages))
+  }
+}
+
+impl Bucket {
+  /// Streams the common prefixes right under the given key prefix page by page, following the
+  /// continuation tokens. The prefix is used as it is, so the common prefixes returned can be
+  /// listed in turn. They are the full keys, ending with the delimiter
+  pub fn prefixes(&self, bucket: &str, prefix: &str, delimiter: &char) -> PrefixPages {
+    let pages = list_request(&self.client, bucket, prefix)
+       .delimiter(delimiter.to_string())
+       .into_paginator()
+       .send()
+       .map(|page| match page {
+         Ok(page) => Ok(
+           page.common_prefixes()
+              .unwrap_or_default()
+              .iter()
+              .filter_map(|p| p.prefix().map(|p| p.to_string()))
+              .collect()
+         ),
+         Err(e) => Err(anyhow::Error::from(e)),
+       });
+
+    Box::pin(pages)
+  }
+}
+
+/// Pages of common prefixes, see [Bucket::prefixes]
+pub type PrefixPages = Pin<Box<dyn Stream<Item=anyhow::Result<Vec<String>>> + Send>>;
+
+/// Sends the pages of every object under the given path. The path is split level by level on
+/// its common prefixes until there are at least `concurrency` shards, objects found along the
+/// way are sent right away, then the shards are listed concurrently
+async fn list_shards(
+  client: Client,
+  parsed: ParsedS3Url,
+  delimiter: char,
+  concurrency: usize,
+  sender: Sender<anyhow::Result<Vec<Object>>>,
+) -> anyhow::Result<()> {
+  let bucket = parsed.bucket_name().to_string();
+  let mut split = ShardSplit::new(list_prefix(&parsed, delimiter), concurrency);
+
+  while let Some(shards) = split.expand() {
+    let mut prefixes = Vec::new();
+    for shard in shards {
+      let mut pages = list_request(&client, &bucket, shard)
+         .delimiter(delimiter.to_string())
+         .into_paginator()
+         .send();
+
+      while let Some(page) = pages.next().await {
+        let page = page?;
+        let objects = page.contents().unwrap_or_default();
+        if !objects.is_empty() {
+          sender.send(Ok(objects.to_vec())).await?;
+        }
+        prefixes.extend(page.common_prefixes().unwrap_or_default().iter().filter_map(|p| p.prefix().map(String::from)));
+      }
+    }
+    split.descend(prefixes);
+  }
+
+  let mut tasks: JoinSet<anyhow::Result<()>> = JoinSet::new();
+  for shard in split.into_shards() {
+    if tasks.len() >= concurrency {
+      if let Some(joined) = tasks.join_next().await {
+        joined??;
+      }
+    }
+
+    let mut pages = list_request(&client, &bucket, &shard)
+       .into_paginator()
+       .send();
+    let sender = sender.clone();
+
+    tasks.spawn(async move {
+      while let Some(page) = pages.next().await {
+        sender.send(Ok(page?.contents().unwrap_or_default().to_vec())).await?;
+      }
+      Ok(())
+    });
+  }
+
+  while let Some(joined) = tasks.join_next().await {
+    joined??;
+  }
+
+  Ok(())
+}
+
+/// Splits the keyspace under a key prefix into shards, one level of common prefixes at a time.
+/// The shards are raw key prefixes, they're never turned into urls which would lose their
+/// empty segments and list some keys twice
+struct ShardSplit {
+  shards: Vec<String>,
+  levels: usize,
+  concurrency: usize,
+}
+
+impl ShardSplit {
+  fn new(prefix: String, concurrency: usize) -> Self {
+    Self {
+      shards: vec![prefix],
+      levels: 0,
+      concurrency,
+    }
+  }
+
+  /// Shards to list one level of with the delimiter, `None` once there are enough of them or
+  /// nothing is left to split
+  fn expand(&self) -> Option<&[String]> {
+    let enough = self.shards.len() >= self.concurrency || self.levels >= MAX_SHARD_LEVELS;
+    match !enough && !self.shards.is_empty() {
+      true => Some(&self.shards),
+      false => None,
+    }
+  }
+
+  /// Replaces the shards with the common prefixes found right under them. The objects next to
+  /// those prefixes were listed along the way, so they're no longer part of any shard
+  fn descend(&mut self, prefixes: Vec<String>) {
+    self.shards = prefixes;
+    self.levels += 1;
+  }
+
+  /// The shards left to list recursively, none when every object was at a level already listed
+  fn into_shards(self) -> Vec<String> {
+    self.shards
+  }
+}
+
+/// A page of a single level listing
+pub struct LevelPage {
+  /// Objects right under the listed path
+  pub objects: Vec<Object>,
+  /// Names of the directories right under the listed path
+  pub directories: Vec<String>,
+}
+
+/// Pages of a single level listing, see [Bucket::level_pages]
+pub type LevelPages = Pin<Box<dyn Stream<Item=anyhow::Result<LevelPage>> + Send>>;
+
+/// Name of the directory a common prefix stands for, relative to the listed prefix
+fn directory_name(prefix: &str, common_prefix: &str, delimiter: char) -> String {
+  common_prefix
+     .strip_prefix(prefix)
+     .unwrap_or(common_prefix)
+     .trim_matches(delimiter)
+     .to_string()
+}
+
+/// Sorts the objects of a listing into what `ls` shows. Objects at the listed level are handed
+/// back right away, deeper objects are only counted towards the directory below the listed
+/// level they're in, so memory grows with the number of directories and not of objects
+pub struct LevelListing {
+  prefix: String,
+  delimiter: char,
+  recursive: bool,
+  directories: BTreeMap<String, S3Directory>,
+}
+
+impl LevelListing {
+  pub fn new(url: &String, delimiter: char, recursive: bool) -> anyhow::Result<Self> {
+    let parsed = ParsedS3Url::parse_from(url, &delimiter)?;
+
+    Ok(Self {
+      prefix: parsed.segments.join(delimiter.to_string().as_str()),
+      delimiter,
+      recursive,
+      directories: BTreeMap::new(),
+    })
+  }
+
+  /// Returns the object when it's shown on its own, otherwise it's added to its directory
+  pub fn push(&mut self, object: Object) -> Option<Object> {
+    if self.recursive {
+      return Some(object);
+    }
+
+    let key = object.key().unwrap_or_default();
+    let relative = key.strip_prefix(self.prefix.as_str()).unwrap_or(key);
+    let mut segments = relative.split(self.delimiter).filter(|s| !s.is_empty());
+
+    let first = segments.next()?;
+    if segments.next().is_none() {
+      return Some(object);
+    }
+
+    let file = S3File {
+      last_modified: object.last_modified().cloned().unwrap_or_else(|| aws_sdk_s3::primitives::DateTime::from_secs(0)),
+      size: object.size(),
+      key: key.to_string(),
+    };
+
+    self.directories
+       .entry(first.to_string())
+       .or_insert_with(|| S3Directory::new(first.to_string()))
+       .add_file(file);
+
+    None
+  }
+
+  /// The directories seen so far, sorted by name
+  pub fn into_directories(self) -> impl Iterator<Item=S3Directory> {
+    self.directories.into_values()
+  }
+}
+
+#[cfg(test)]
+mod listing_tests {
+  use super::*;
+
+  fn object(key: &str, size: i64) -> Object {
+    Object::builder()
+       .key(key)
+       .size(size)
+       .last_modified(aws_sdk_s3::primitives::DateTime::from_secs(size))
+       .build()
+  }
+
+  #[test]
+  fn test_level_listing() {
+    let mut listing = LevelListing::new(&String::from("s3://bucket/logs"), '/', false).unwrap();
+
+    assert!(listing.push(object("logs/readme.md", 1)).is_some());
+    assert!(listing.push(object("logs/2023/01/app.log", 10)).is_none());
+    assert!(listing.push(object("logs/2023/02/app.log", 20)).is_none());
+    assert!(listing.push(object("logs/2024/app.log", 5)).is_none());
+
+    let directories = listing.into_directories().collect::<Vec<_>>();
+    assert_eq!(directories.len(), 2);
+    assert_eq!(directories[0].name, "2023");
+    assert_eq!(directories[0].size(), 30);
+    assert_eq!(directories[0].len(), 2);
+    assert_eq!(directories[0].last_modified().secs(), 20);
+    assert_eq!(directories[1].name, "2024");
+
+    let mut recursive = LevelListing::new(&String::from("s3://bucket/logs"), '/', true).unwrap();
+    assert!(recursive.push(object("logs/2023/01/app.log", 10)).is_some());
+    assert_eq!(recursive.into_directories().count(), 0);
+  }
+
+  #[test]
+  fn test_shard_split() {
+    let mut split = ShardSplit::new(String::from("logs/"), 4);
+    assert_eq!(split.expand(), Some(&[String::from("logs/")][..]));
+
+    // Empty segments are kept, `a//` and `a/` are different shards
+    split.descend(vec![String::from("logs/a/"), String::from("logs/a//")]);
+    assert_eq!(split.expand().map(|s| s.len()), Some(2));
+
+    split.descend(vec![
+      String::from("logs/a/1/"),
+      String::from("logs/a/2/"),
+      String::from("logs/a//1/"),
+      String::from("logs/a//2/"),
+    ]);
+    assert_eq!(split.expand(), None);
+    assert_eq!(split.into_shards(), vec!["logs/a/1/", "logs/a/2/", "logs/a//1/", "logs/a//2/"]);
+
+    // Nothing is left to list when the last level had no common prefixes
+    let mut flat = ShardSplit::new(String::new(), 4);
+    flat.descend(Vec::new());
+    assert_eq!(flat.expand(), None);
+    assert!(flat.into_shards().is_empty());
+  }
+
+  #[test]
+  fn test_directory_name() {
+    assert_eq!(directory_name("logs", "logs/2023/", '/'), "2023");
+    assert_eq!(directory_name("", "logs/", '/'), "logs");
+    assert_eq!(directory_name("a:b", "a:b:c:", ':'), "c");
+  }
+}
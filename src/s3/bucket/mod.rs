@@ -1,25 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
-use std::time::SystemTime;
 
 use aws_sdk_s3::{Client, Config};
 use aws_sdk_s3::config::Credentials;
+use aws_sdk_s3::error::ProvideErrorMetadata;
 use aws_sdk_s3::operation::list_buckets::{ListBucketsError, ListBucketsOutput};
-use aws_sdk_s3::operation::list_objects_v2::{ListObjectsV2Error, ListObjectsV2Output};
 use aws_sdk_s3::operation::list_objects_v2::builders::ListObjectsV2FluentBuilder;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::types::BucketCannedAcl;
 use colored::Colorize;
 use console::Emoji;
 use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use tokio::io::AsyncWriteExt;
+use tokio_stream::StreamExt;
 use tokio::time::Instant;
 
+use crate::commands::cat::CatOpts;
 use crate::commands::copy::CopyOpts;
 use crate::commands::du::DuOpts;
 use crate::commands::list::ListOpts;
+use crate::commands::make_bucket::MakeBucketOpts;
+use crate::commands::presign::{PresignMethod, PresignOpts};
+use crate::commands::remove::RemoveOpts;
+use crate::commands::remove_bucket::RemoveBucketOpts;
 use crate::error::S3Error;
-use crate::s3::content::{S3Directory, S3File};
+use crate::s3::bucket::delete::report_delete_failures;
+use crate::s3::bucket::listing::LevelListing;
+use crate::s3::bucket::output::{du_groups, DuOutput};
+use crate::s3::bucket::stdin::STDIN_PATH;
+use crate::s3::bucket::transfer::relative_key;
+use crate::s3::content::S3Directory;
 use crate::s3::ParsedS3Url;
 
+pub mod acl;
+pub mod checksum;
+pub mod delete;
+pub mod download;
+pub mod journal;
+pub mod listing;
+pub mod multipart;
 pub mod output;
+pub mod stat;
+pub mod stdin;
+pub mod sync;
+pub mod transfer;
+pub mod tree;
 
 static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍  ", "");
 static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", ":-)");
@@ -33,17 +58,25 @@ pub struct Bucket {
 
 fn get_list_object_request(client: &Client, url: &String, delimiter: &char) -> anyhow::Result<ListObjectsV2FluentBuilder> {
   let parsed = parse_url(url, delimiter);
+  Ok(list_request(client, &parsed.bucket_name, &list_prefix(&parsed, *delimiter)))
+}
+
+/// Lists the keys starting with the given prefix as it is, without parsing it as a url
+fn list_request(client: &Client, bucket: &str, prefix: &str) -> ListObjectsV2FluentBuilder {
+  client
+     .list_objects_v2()
+     .bucket(bucket)
+     .prefix(prefix)
+     .max_keys(1000)
+}
+
+/// Key prefix of everything under the url, the segments followed by the delimiter
+pub fn list_prefix(parsed: &ParsedS3Url, delimiter: char) -> String {
   let mut prefix = parsed.segments.join(delimiter.to_string().as_str());
   if !prefix.is_empty() {
-    prefix.push(*delimiter);
+    prefix.push(delimiter);
   }
-  Ok(
-    client
-       .list_objects_v2()
-       .bucket(parsed.bucket_name.to_string())
-       .prefix(prefix)
-       .max_keys(1000)
-  )
+  prefix
 }
 
 fn parse_url(url: &String, delimiter: &char) -> ParsedS3Url {
@@ -62,8 +95,10 @@ impl Bucket {
          Credentials::new(
            access_key.to_string(),
            secret_key.to_string(),
-           Some(String::from("")),
-           Some(SystemTime::now()),
+           // Static keys have no session token and never expire, both would otherwise end up
+           // in the query string of presigned URLs
+           None,
+           None,
            "s3cli",
          )
        )
@@ -83,64 +118,46 @@ impl Bucket {
 
   /// Lists contents of a S3 bucket
   pub async fn ls(&self, opts: ListOpts) -> anyhow::Result<ListOutput> {
-    let mut next_token: Option<String> = None;
-    let mut objects: Vec<aws_sdk_s3::types::Object> = Vec::new();
     let path = opts.path.unwrap();
-    loop {
-
-      // Get the next page of results
-      let response = get_list_object_request(&self.client, &path, &opts.delimiter)?
-         .set_continuation_token(next_token.take())
-         .send()
-         .await?;
-
-      // Add up the file sizes we got back
-      if let Some(contents) = response.contents() {
-        for object in contents {
-          objects.push(object.clone());
+    let mut pages = self.object_pages(&path, &opts.delimiter)?;
+    let mut listing = LevelListing::new(&path, opts.delimiter, opts.recursive)?;
+    let mut output = ListOutput::new();
+
+    while let Some(page) = pages.next().await {
+      for object in page? {
+        if let Some(object) = listing.push(object) {
+          output.objects.push(object);
         }
       }
-
-      // Handle pagination, and break the loop if there are no more pages
-      next_token = response.continuation_token().map(|t| t.to_string());
-      if !response.is_truncated() {
-        break;
-      }
     }
 
-    let result = filter_objects_by_path(
-      objects,
-      &path,
-      opts.delimiter,
-      opts.recursive,
-    );
+    for directory in listing.into_directories() {
+      output.directories.insert(directory.name.clone(), directory);
+    }
 
-    Ok(result?)
+    Ok(output)
   }
 
-  pub async fn prefixes(&self, url: &String, delimiter: &char) -> Result<ListObjectsV2Output, S3Error> {
-    let request = get_list_object_request(&self.client, url, delimiter)
-       .unwrap()
-       .delimiter(delimiter.to_string());
-
-    let output = request
-       .send()
-       .await
-       .unwrap_or_else(|e| {
-         S3Error::printout_s3sdk_error::<ListObjectsV2Error>(e);
-         std::process::exit(1);
-       });
-
-    Ok(output)
+  /// Lists every object under the given path, following the continuation tokens
+  pub async fn list_objects(&self, url: &String, delimiter: &char) -> anyhow::Result<Vec<aws_sdk_s3::types::Object>> {
+    let mut pages = self.object_pages(url, delimiter)?;
+    let mut objects: Vec<aws_sdk_s3::types::Object> = Vec::new();
+    while let Some(page) = pages.next().await {
+      objects.extend(page?);
+    }
+    Ok(objects)
   }
 
   /// Lists all objects in an S3 bucket with the given prefix, and adds up their size.
-  pub async fn du(&self, opts: DuOpts) -> anyhow::Result<usize> {
-    let mut next_token: Option<String> = None;
+  pub async fn du(&self, opts: DuOpts) -> anyhow::Result<DuOutput> {
     let mut total_size_bytes = 0;
     let mut total_objects = 0;
+    let mut prefixes: BTreeMap<String, (usize, usize)> = BTreeMap::new();
     let started = Instant::now();
 
+    let parsed = ParsedS3Url::parse_from(&opts.prefix, &opts.delimiter)?;
+    let prefix = parsed.segments.join(opts.delimiter.to_string().as_str());
+
     // Print a spinner indicator
     let pb = ProgressBar::new_spinner();
     let spinner_style = ProgressStyle::with_template("{prefix:.bold.dim} {spinner} {wide_msg}")
@@ -153,42 +170,35 @@ impl Bucket {
       pb.finish_and_clear()
     }
 
-    // Loop until we've gotten all the pages of results
-    loop {
-      // Get the next page of results
-      let response = get_list_object_request(&self.client, &opts.prefix, &opts.delimiter)?
-         .set_continuation_token(next_token.take())
-         .send()
-         .await?;
-
-      // Add up the file sizes we got back
-      if let Some(contents) = response.contents() {
-        for object in contents {
-          total_size_bytes += object.size() as usize;
-          total_objects += 1;
+    // Add up the file sizes as the pages come in
+    let mut pages = self.sharded_object_pages(&opts.prefix, &opts.delimiter, opts.list_concurrency)?;
+    while let Some(page) = pages.next().await {
+      for object in page? {
+        total_size_bytes += object.size() as usize;
+        total_objects += 1;
+
+        if let Some(depth) = opts.depth {
+          for group in du_groups(&prefix, object.key().unwrap_or_default(), opts.delimiter, depth) {
+            let totals = prefixes.entry(group).or_default();
+            totals.0 += object.size() as usize;
+            totals.1 += 1;
+          }
         }
       }
 
       if false == pb.is_finished() {
         pb.set_message(format!("{} {}", LOOKING_GLASS, "Looking up objects..."));
       }
-
-      // Handle pagination, and break the loop if there are no more pages
-      next_token = response.next_continuation_token().map(|t| t.to_string());
-      if !response.is_truncated() || next_token.is_none() {
-        break;
-      }
     }
 
+    // Like the spinner, the summary goes to stderr so it never mixes with json, ndjson or csv
     if false == pb.is_finished() {
       pb.finish_and_clear();
-      println!("{} {}", "Total Objects:".bold(), total_objects);
-      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+      eprintln!("{} {}", "Total Objects:".bold(), total_objects);
+      eprintln!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
     }
 
-    if *&opts.verbose {}
-
-    Ok(total_size_bytes)
+    Ok(DuOutput::new(total_size_bytes, total_objects).with_prefixes(prefixes))
   }
 
   /// Lists all buckets in an S3 account.
@@ -209,140 +219,211 @@ impl Bucket {
 
   /// Copies from content from a bucket to a destination
   pub async fn cp(&self, opts: CopyOpts) -> anyhow::Result<()> {
-    Ok(())
+    let from_s3 = ParsedS3Url::is_s3url(&opts.from);
+    let to_s3 = ParsedS3Url::is_s3url(&opts.to);
+
+    match (from_s3, to_s3) {
+      (false, true) if opts.from == STDIN_PATH => self.upload_stdin(&opts).await,
+      (false, true) => self.upload(&opts).await,
+      (true, false) => self.download(&opts).await,
+      (true, true) => self.copy_remote(&opts).await,
+      _ => Err(anyhow::anyhow!("Copying from {} to {} is not supported", &opts.from, &opts.to)),
+    }
   }
 
-  pub async fn mv(&self, from: &String, to: &String) -> Result<(), S3Error> {
-    Ok(())
+  /// Moves content to a destination, each source is removed once its copy has been verified
+  pub async fn mv(&self, opts: CopyOpts) -> anyhow::Result<()> {
+    self.cp(CopyOpts { remove_source: true, ..opts }).await
   }
 
-  pub async fn rm(&self, path: &String) -> Result<(), S3Error> {
-    Ok(())
+  /// Streams an object, or the requested range of it, to stdout as it's received
+  pub async fn cat(&self, opts: CatOpts) -> anyhow::Result<()> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let key = parsed.segments.join(opts.delimiter.to_string().as_str());
+
+    if key.is_empty() {
+      return Err(anyhow::anyhow!("An object key is required"));
+    }
+
+    let response = self.client
+       .get_object()
+       .bucket(&parsed.bucket_name)
+       .key(&key)
+       .set_range(opts.range_header())
+       .send()
+       .await?;
+
+    let mut body = response.body.into_async_read();
+    let mut stdout = tokio::io::stdout();
+
+    // A reader that stops early, like `head`, closes the pipe which is not an error
+    let result = async {
+      tokio::io::copy(&mut body, &mut stdout).await?;
+      stdout.flush().await
+    }.await;
+
+    match result {
+      Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.into()),
+      _ => Ok(()),
+    }
   }
-}
 
-/// Receives a list of object and a path and returns a list of objects that matches the path
-fn filter_objects_by_path(
-  objects: Vec<aws_sdk_s3::types::Object>,
-  path: &str,
-  delimiter: char,
-  recursive: bool,
-) -> anyhow::Result<ListOutput> {
-  let parsed = ParsedS3Url::parse_from(&path.to_string(), &delimiter);
-  if let Err(e) = parsed {
-    return Err(anyhow::anyhow!("{} {:?}", "error:".red(), e.to_string()));
-  };
-
-  let prefix = ensure_trailing_slash(
-    &path.to_string()
-       .replace("s3://", "")
-       .replace(parsed.as_ref().unwrap().bucket_name.as_str(), "")
-  );
-
-  let mut output = ListOutput::new();
-
-  for object in objects {
-    let mut no_prefixed_key = ensure_surrounding_slashes(object.key.as_ref().unwrap());
-
-    if prefix != "/" {
-      no_prefixed_key = no_prefixed_key.replace(&prefix, "");
+  /// Creates a presigned URL for downloading or uploading an object without credentials
+  pub async fn presign(&self, opts: PresignOpts) -> anyhow::Result<String> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let key = parsed.segments.join(opts.delimiter.to_string().as_str());
+
+    if key.is_empty() {
+      return Err(anyhow::anyhow!("An object key is required"));
     }
 
-    let key_segments = no_prefixed_key
-       .split(&delimiter.to_string())
-       .filter(|&x| !x.is_empty())
-       .collect::<Vec<&str>>();
+    let config = PresigningConfig::expires_in(opts.expires)?;
+
+    let request = match opts.method {
+      PresignMethod::Get => self.client
+         .get_object()
+         .bucket(&parsed.bucket_name)
+         .key(&key)
+         .presigned(config)
+         .await?,
+      PresignMethod::Put => self.client
+         .put_object()
+         .bucket(&parsed.bucket_name)
+         .key(&key)
+         .set_content_type(opts.content_type)
+         .presigned(config)
+         .await?,
+    };
 
-    let first_segment = key_segments.get(0);
+    Ok(request.uri().to_string())
+  }
 
-    if first_segment.is_some() {
-      let key = first_segment.unwrap();
-      // if there is not more one segments it means we're same level as the prefix
-      // otherwise we're deeper than the prefix, use recursive flag to decide if we should include it
-      if key_segments.len() == 1 {
-        output.add_object(object.clone());
-        continue;
+  /// Removes an object, or every object under a prefix when recursive
+  pub async fn rm(&self, opts: RemoveOpts) -> anyhow::Result<()> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let prefix = parsed.segments.join(opts.delimiter.to_string().as_str());
+
+    if !opts.recursive {
+      if prefix.is_empty() {
+        return Err(anyhow::anyhow!("An object key is required, use --recursive to remove a whole prefix"));
+      }
+
+      if opts.dry_run {
+        println!("(dryrun) delete: s3://{}/{}", parsed.bucket_name, prefix);
+        return Ok(());
       }
 
-      if false == recursive {
-        // it's a directory, in the last statement we collecting objects at same level of the prefix
-        let directory = S3Directory::new(key.to_string());
-        let directory = output.get_or_insert_directory(directory);
-        let file = S3File {
-          last_modified: object.last_modified.unwrap(),
-          size: object.size.clone(),
-          key: object.key.clone().unwrap(),
-        };
-        directory.add_file(file);
+      self.delete_object(&parsed.bucket_name, &prefix).await?;
+
+      println!("delete: s3://{}/{}", parsed.bucket_name, prefix);
+      return Ok(());
+    }
+
+    // Every page holds at most 1000 keys, as many as a single DeleteObjects takes, so each page
+    // is deleted as soon as it arrives
+    let mut failures = Vec::new();
+    let mut total = 0;
+    let mut pages = self.object_pages(&opts.path, &opts.delimiter)?;
+    while let Some(page) = pages.next().await {
+      let keys = page?
+         .into_iter()
+         .filter_map(|object| object.key)
+         .filter(|key| relative_key(key, &prefix, opts.delimiter, &opts.exclude).is_some())
+         .collect::<Vec<_>>();
+
+      if opts.dry_run {
+        for key in &keys {
+          println!("(dryrun) delete: s3://{}/{}", parsed.bucket_name, key);
+        }
         continue;
       }
 
-      // on recursive mode we're checking if key of object starts with prefix
-      if prefix.is_empty() || prefix == "/" {
-        output.add_object(object.clone());
+      let page_failures = self.delete_objects(&parsed.bucket_name, &keys).await?;
+      for key in keys.iter().filter(|k| !page_failures.iter().any(|f| &f.key == *k)) {
+        println!("delete: s3://{}/{}", parsed.bucket_name, key);
       }
+
+      total += keys.len();
+      failures.extend(page_failures);
     }
+
+    report_delete_failures(&parsed.bucket_name, &failures, total)
   }
 
-  Ok(output)
-}
+  /// Creates a bucket, with the given canned ACL when there is one
+  pub async fn mb(&self, opts: MakeBucketOpts) -> anyhow::Result<()> {
+    let bucket_name = bucket_name_of(&opts.path)?;
 
-#[derive(Debug)]
-pub struct ListOutput {
-  pub objects: Vec<aws_sdk_s3::types::Object>,
-  pub directories: HashMap<String, S3Directory>,
-}
+    self.client
+       .create_bucket()
+       .bucket(&bucket_name)
+       .set_acl(opts.acl.map(|acl| BucketCannedAcl::from(acl.to_string().as_str())))
+       .send()
+       .await?;
 
-impl ListOutput {
-  fn new() -> Self {
-    Self {
-      objects: Vec::new(),
-      directories: HashMap::new(),
-    }
+    println!("make_bucket: s3://{}", bucket_name);
+    Ok(())
   }
 
-  fn add_object(&mut self, object: aws_sdk_s3::types::Object) {
-    // first checking if not already exists
-    let mut found = false;
-    for obj in self.objects.iter_mut() {
-      if obj.key == object.key {
-        found = true;
-        break;
+  /// Removes a bucket, with force every object in it is deleted first
+  pub async fn rb(&self, opts: RemoveBucketOpts) -> anyhow::Result<()> {
+    let bucket_name = bucket_name_of(&opts.path)?;
+
+    if opts.force {
+      let keys = self.list_objects(&format!("s3://{}", bucket_name), &'/')
+         .await?
+         .into_iter()
+         .filter_map(|object| object.key)
+         .collect::<Vec<_>>();
+
+      let failures = self.delete_objects(&bucket_name, &keys).await?;
+      for key in keys.iter().filter(|k| !failures.iter().any(|f| &f.key == *k)) {
+        println!("delete: s3://{}/{}", bucket_name, key);
       }
+      report_delete_failures(&bucket_name, &failures, keys.len())?;
     }
-    // otherwise add it
-    if !found {
-      self.objects.push(object);
+
+    if let Err(e) = self.client.delete_bucket().bucket(&bucket_name).send().await {
+      let e = e.into_service_error();
+      if e.code() == Some("BucketNotEmpty") && !opts.force {
+        return Err(anyhow::anyhow!("s3://{} is not empty, use --force to delete its contents first", bucket_name));
+      }
+      return Err(e.into());
     }
-  }
 
-  fn get_or_insert_directory(&mut self, directory: S3Directory) -> &mut S3Directory {
-    let dir = self.directories.entry(directory.name.clone()).or_insert(directory);
-    dir
+    println!("remove_bucket: s3://{}", bucket_name);
+    Ok(())
   }
 }
 
-/// Ensures that a path ends with a trailing slash
-fn ensure_trailing_slash(path: &String) -> String {
-  if !path.ends_with("/") {
-    return format!("{}/", path);
+/// Reads the bucket name of a URL which must not point inside the bucket
+fn bucket_name_of(url: &String) -> anyhow::Result<String> {
+  let parsed = ParsedS3Url::parse_from(url, &'/')?;
+  if !parsed.segments.is_empty() {
+    return Err(anyhow::anyhow!("Expected a bucket (e.g., s3://bucket) but got {}", url));
   }
-  path.to_string()
+  Ok(parsed.bucket_name)
 }
 
-/// Ensure surrounding slashes
-fn ensure_surrounding_slashes(path: &String) -> String {
-  if !path.starts_with("/") {
-    return format!("/{}", path);
-  }
-  if !path.ends_with("/") {
-    return format!("{}/", path);
+#[derive(Debug)]
+pub struct ListOutput {
+  pub objects: Vec<aws_sdk_s3::types::Object>,
+  pub directories: HashMap<String, S3Directory>,
+}
+
+impl ListOutput {
+  fn new() -> Self {
+    Self {
+      objects: Vec::new(),
+      directories: HashMap::new(),
+    }
   }
-  path.to_string()
 }
 
 #[cfg(test)]
 mod s3_tests {
+  use crate::commands::output::OutputFormat;
+
   use super::*;
 
   fn setup(large: bool) -> Bucket {
@@ -356,6 +437,16 @@ mod s3_tests {
     Bucket::new(endpoint, access_key, secret_key)
   }
 
+  #[test]
+  fn test_list_prefix() {
+    let parsed = ParsedS3Url::parse_from(&String::from("s3://bucket:logs:2023"), &':').unwrap();
+    assert_eq!(parsed.bucket_name(), "bucket");
+    assert_eq!(list_prefix(&parsed, ':'), "logs:2023:");
+
+    let parsed = ParsedS3Url::parse_from(&String::from("s3://bucket/"), &'/').unwrap();
+    assert_eq!(list_prefix(&parsed, '/'), "");
+  }
+
   #[tokio::test]
   async fn test_using_client_directly() {
     let bucket = setup(true);
@@ -380,6 +471,9 @@ mod s3_tests {
       exclude: Vec::new(),
       human_readable: true,
       verbose: true,
+      output: OutputFormat::Text,
+      dir_sizes: true,
+      list_concurrency: 1,
     };
 
     let result = bucket.ls(opts.clone()).await;
@@ -0,0 +1,349 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::Client;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use colored::Colorize;
+use indicatif::ProgressBar;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::task::JoinSet;
+
+use crate::s3::bucket::Bucket;
+use crate::s3::bucket::checksum::SHA256_METADATA_KEY;
+use crate::s3::bucket::journal::{stale_journals, UploadJournal};
+use crate::s3::bucket::transfer::{object_acl, TransferOpts};
+
+/// Smallest part size accepted by S3, except for the last part of an upload
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Largest part size accepted by S3
+pub const MAX_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Part size used when none is given, files larger than this are uploaded in parts
+pub const DEFAULT_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Number of parts uploaded at the same time when none is given
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Maximum number of parts allowed in a multipart upload
+pub const MAX_PARTS: u64 = 10_000;
+
+impl Bucket {
+  /// Uploads a local file in parts with CreateMultipartUpload, UploadPart and CompleteMultipartUpload.
+  /// At most `concurrency` parts are read into memory and in flight at the same time.
+  ///
+  /// Progress is kept in an [UploadJournal], when the upload fails it's left open on the server
+  /// and running the same upload again only sends the missing parts. Uploads left open by an
+  /// earlier version of the file are aborted before a new one starts. Without a journal the
+  /// upload is aborted on failure.
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) async fn upload_multipart(
+    &self,
+    path: &Path,
+    sha256: &str,
+    bucket: &str,
+    key: &str,
+    transfer: &TransferOpts,
+    pb: &ProgressBar,
+  ) -> anyhow::Result<()> {
+    let length = tokio::fs::metadata(path).await?.len();
+    let journal_path = UploadJournal::path_for(&self.endpoint, bucket, key, path).ok();
+
+    let mut journal = match &journal_path {
+      Some(journal_path) => self.resume_journal(journal_path, bucket, key).await?,
+      None => None,
+    };
+
+    if journal.is_none() {
+      if let Some(journal_path) = &journal_path {
+        self.abort_stale_uploads(journal_path, bucket, key).await;
+      }
+
+      let part_size = effective_part_size(length, transfer.part_size);
+      let upload = self.client
+         .create_multipart_upload()
+         .bucket(bucket)
+         .key(key)
+         .metadata(SHA256_METADATA_KEY, sha256)
+         .set_acl(transfer.acl.map(object_acl))
+         .send()
+         .await?;
+
+      let upload_id = match upload.upload_id() {
+        Some(id) => id.to_string(),
+        None => return Err(anyhow::anyhow!("No upload id was returned for {}", key)),
+      };
+
+      journal = match &journal_path {
+        Some(journal_path) => match UploadJournal::create(journal_path, &upload_id, part_size) {
+          Ok(journal) => Some(journal),
+          Err(e) => {
+            self.abort_multipart(bucket, key, &upload_id).await;
+            return Err(e);
+          }
+        },
+        None => Some(UploadJournal::in_memory(&upload_id, part_size)),
+      };
+    }
+
+    let mut journal = journal.unwrap();
+    let upload_id = journal.upload_id.clone();
+
+    let result = async {
+      let parts = self.upload_parts(path, bucket, key, length, transfer.concurrency, &mut journal, pb).await?;
+      self.complete_multipart(bucket, key, &upload_id, parts).await
+    }.await;
+
+    match &result {
+      Ok(_) => journal.remove(),
+      Err(_) if journal.is_persisted() => {
+        eprintln!(
+          "{} Upload of {} was interrupted, run the same command again to resume it",
+          "warn:".yellow(),
+          path.display()
+        );
+      }
+      Err(_) => self.abort_multipart(bucket, key, &upload_id).await,
+    }
+
+    result
+  }
+
+  /// Loads the journal of a previous attempt, it's discarded only when the server no longer
+  /// knows about the upload. Any other error is returned and the journal is kept, so a flaky
+  /// connection doesn't throw away the parts uploaded so far
+  async fn resume_journal(&self, journal_path: &Path, bucket: &str, key: &str) -> anyhow::Result<Option<UploadJournal>> {
+    let journal = match UploadJournal::load(journal_path).ok().flatten() {
+      Some(journal) => journal,
+      None => return Ok(None),
+    };
+
+    let result = self.client
+       .list_parts()
+       .bucket(bucket)
+       .key(key)
+       .upload_id(&journal.upload_id)
+       .max_parts(1)
+       .send()
+       .await;
+
+    if let Err(e) = result {
+      let e = e.into_service_error();
+      if e.code() == Some("NoSuchUpload") {
+        journal.remove();
+        return Ok(None);
+      }
+      return Err(e.into());
+    }
+
+    Ok(Some(journal))
+  }
+
+  /// Aborts the uploads left open by earlier versions of the file for the same destination and
+  /// removes their journals, they can't be resumed and their parts would stay on the server
+  async fn abort_stale_uploads(&self, journal_path: &Path, bucket: &str, key: &str) {
+    for stale in stale_journals(journal_path).unwrap_or_default() {
+      if let Ok(Some(journal)) = UploadJournal::load(&stale) {
+        self.abort_multipart(bucket, key, &journal.upload_id).await;
+        eprintln!(
+          "{} Aborted the stale upload {} of s3://{}/{}",
+          "warn:".yellow(),
+          journal.upload_id,
+          bucket,
+          key
+        );
+      }
+      let _ = std::fs::remove_file(&stale);
+    }
+  }
+
+  /// Uploads every part of the file missing from the journal and returns all of them sorted by
+  /// part number
+  #[allow(clippy::too_many_arguments)]
+  async fn upload_parts(
+    &self,
+    path: &Path,
+    bucket: &str,
+    key: &str,
+    length: u64,
+    concurrency: usize,
+    journal: &mut UploadJournal,
+    pb: &ProgressBar,
+  ) -> anyhow::Result<Vec<CompletedPart>> {
+    let mut tasks: JoinSet<anyhow::Result<(CompletedPart, u64)>> = JoinSet::new();
+    let mut parts: Vec<CompletedPart> = Vec::new();
+
+    for (part_number, offset, size) in part_ranges(length, journal.part_size) {
+      // Parts finished by a previous attempt are only counted
+      if let Some(e_tag) = journal.parts.get(&part_number) {
+        parts.push(completed_part(part_number, Some(e_tag.to_string())));
+        if !pb.is_finished() {
+          pb.inc(size);
+        }
+        continue;
+      }
+
+      // Wait for a slot before reading the next part into memory
+      if tasks.len() >= concurrency.max(1) {
+        if let Some(joined) = tasks.join_next().await {
+          parts.push(record_part(joined??, journal, pb)?);
+        }
+      }
+
+      let part = PartUpload {
+        client: self.client.clone(),
+        path: path.to_path_buf(),
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        upload_id: journal.upload_id.clone(),
+        part_number,
+        offset,
+        size,
+      };
+      tasks.spawn(async move { part.send().await });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+      parts.push(record_part(joined??, journal, pb)?);
+    }
+
+    parts.sort_by_key(|p| p.part_number());
+
+    Ok(parts)
+  }
+
+  /// Completes a multipart upload with the given parts
+  pub(crate) async fn complete_multipart(
+    &self,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    parts: Vec<CompletedPart>,
+  ) -> anyhow::Result<()> {
+    self.client
+       .complete_multipart_upload()
+       .bucket(bucket)
+       .key(key)
+       .upload_id(upload_id)
+       .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+       .send()
+       .await?;
+
+    Ok(())
+  }
+
+  /// Aborts a multipart upload so the uploaded parts don't linger on the server, errors are ignored
+  /// since it's only called while already failing
+  pub(crate) async fn abort_multipart(&self, bucket: &str, key: &str, upload_id: &str) {
+    let _ = self.client
+       .abort_multipart_upload()
+       .bucket(bucket)
+       .key(key)
+       .upload_id(upload_id)
+       .send()
+       .await;
+  }
+}
+
+/// A single part of a file, owned so it can be sent from a spawned task
+struct PartUpload {
+  client: Client,
+  path: PathBuf,
+  bucket: String,
+  key: String,
+  upload_id: String,
+  part_number: i32,
+  offset: u64,
+  size: u64,
+}
+
+impl PartUpload {
+  async fn send(self) -> anyhow::Result<(CompletedPart, u64)> {
+    let mut file = tokio::fs::File::open(&self.path).await?;
+    file.seek(SeekFrom::Start(self.offset)).await?;
+
+    let mut buffer = vec![0u8; self.size as usize];
+    file.read_exact(&mut buffer).await?;
+
+    let response = self.client
+       .upload_part()
+       .bucket(&self.bucket)
+       .key(&self.key)
+       .upload_id(&self.upload_id)
+       .part_number(self.part_number)
+       .content_length(self.size as i64)
+       .body(ByteStream::from(buffer))
+       .send()
+       .await?;
+
+    let part = completed_part(self.part_number, response.e_tag().map(|t| t.to_string()));
+
+    Ok((part, self.size))
+  }
+}
+
+pub(crate) fn completed_part(part_number: i32, e_tag: Option<String>) -> CompletedPart {
+  CompletedPart::builder()
+     .part_number(part_number)
+     .set_e_tag(e_tag)
+     .build()
+}
+
+/// Writes a finished part to the journal and advances the progress bar
+fn record_part(
+  (part, size): (CompletedPart, u64),
+  journal: &mut UploadJournal,
+  pb: &ProgressBar,
+) -> anyhow::Result<CompletedPart> {
+  journal.record_part(part.part_number(), part.e_tag().unwrap_or_default())?;
+  if !pb.is_finished() {
+    pb.inc(size);
+  }
+  Ok(part)
+}
+
+/// Grows the part size when needed so the file fits in the maximum number of parts
+pub fn effective_part_size(length: u64, part_size: u64) -> u64 {
+  let part_size = part_size.max(MIN_PART_SIZE);
+  part_size.max(length.div_ceil(MAX_PARTS))
+}
+
+/// Splits a file into (part number, offset, size) ranges, part numbers start at 1
+pub fn part_ranges(length: u64, part_size: u64) -> Vec<(i32, u64, u64)> {
+  let mut ranges = Vec::new();
+  let mut offset = 0;
+  let mut part_number = 1;
+  while offset < length {
+    let size = part_size.min(length - offset);
+    ranges.push((part_number, offset, size));
+    offset += size;
+    part_number += 1;
+  }
+  ranges
+}
+
+#[cfg(test)]
+mod multipart_tests {
+  use super::*;
+
+  #[test]
+  fn test_part_ranges() {
+    let ranges = part_ranges(12, 5);
+    assert_eq!(ranges, vec![(1, 0, 5), (2, 5, 5), (3, 10, 2)]);
+    assert!(part_ranges(0, 5).is_empty());
+  }
+
+  #[test]
+  fn test_effective_part_size() {
+    assert_eq!(effective_part_size(100, 1024), MIN_PART_SIZE);
+    assert_eq!(effective_part_size(100, DEFAULT_PART_SIZE), DEFAULT_PART_SIZE);
+
+    // 200 GiB doesn't fit in 10,000 parts of 8 MiB
+    let length = 200 * 1024 * 1024 * 1024;
+    let part_size = effective_part_size(length, DEFAULT_PART_SIZE);
+    assert!(part_size > DEFAULT_PART_SIZE);
+    assert!(part_ranges(length, part_size).len() as u64 <= MAX_PARTS);
+  }
+}
@@ -1,6 +1,293 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::s3::content::S3Directory;
+use crate::utc_datetime;
+
+/// Columns of a listing in csv and table output
+pub const LIST_COLUMNS: [&str; 5] = ["date", "type", "size", "key", "storage_class"];
+
+/// Index of the size column in [LIST_COLUMNS]
+pub const LIST_SIZE_COLUMN: usize = 2;
+
+/// Columns of a disk usage report in csv and table output
+pub const DU_COLUMNS: [&str; 3] = ["prefix", "objects", "size"];
+
+#[derive(Debug, Serialize)]
 pub struct DuOutput {
   pub total_size_bytes: usize,
   pub total_size_human: String,
   pub total_objects: usize,
-  pub objects: Vec<aws_sdk_s3::types::Object>,
-}
\ No newline at end of file
+  /// Cumulative totals of every prefix up to the given depth below the listed prefix, sorted by
+  /// prefix. Every object counts towards each prefix above it, objects right under the listed
+  /// prefix are only counted in the total
+  pub prefixes: Vec<DuPrefix>,
+}
+
+/// Total of the objects sharing a key prefix
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DuPrefix {
+  pub prefix: String,
+  pub total_size_bytes: usize,
+  pub total_size_human: String,
+  pub total_objects: usize,
+}
+
+impl DuOutput {
+  pub fn new(total_size_bytes: usize, total_objects: usize) -> Self {
+    Self {
+      total_size_bytes,
+      total_size_human: human_bytes::human_bytes(total_size_bytes as f64),
+      total_objects,
+      prefixes: Vec::new(),
+    }
+  }
+
+  /// Adds the totals of every prefix, given as prefix => (size, objects)
+  pub fn with_prefixes(mut self, prefixes: BTreeMap<String, (usize, usize)>) -> Self {
+    self.prefixes = prefixes
+       .into_iter()
+       .map(|(prefix, (size, objects))| DuPrefix {
+         prefix,
+         total_size_bytes: size,
+         total_size_human: human_bytes::human_bytes(size as f64),
+         total_objects: objects,
+       })
+       .collect();
+    self
+  }
+
+  /// Row of the report for the given prefix, in the order of [DU_COLUMNS]
+  pub fn row(&self, prefix: &str, human_size: bool) -> Vec<String> {
+    vec![
+      prefix.to_string(),
+      self.total_objects.to_string(),
+      format_size(self.total_size_bytes, human_size),
+    ]
+  }
+}
+
+impl DuPrefix {
+  /// Row of the prefix, in the order of [DU_COLUMNS]
+  pub fn row(&self, human_size: bool) -> Vec<String> {
+    vec![
+      self.prefix.clone(),
+      self.total_objects.to_string(),
+      format_size(self.total_size_bytes, human_size),
+    ]
+  }
+}
+
+/// Returns every prefix a key is counted under when grouping up to `depth` segments after the
+/// listed prefix. Like `du -d` the totals are cumulative, so a key counts towards each of the
+/// directories above it, from the one right under the listed prefix down to `depth` levels
+pub fn du_groups(prefix: &str, key: &str, delimiter: char, depth: usize) -> Vec<String> {
+  let relative = key.strip_prefix(prefix).unwrap_or(key).trim_start_matches(delimiter);
+
+  // The last segment is the name of the object, only the directories above it are grouped
+  let mut directories = relative.split(delimiter).collect::<Vec<_>>();
+  directories.pop();
+
+  let mut group = prefix.to_string();
+  let mut groups = Vec::new();
+  for segment in directories.into_iter().filter(|s| !s.is_empty()).take(depth) {
+    if !group.is_empty() && !group.ends_with(delimiter) {
+      group.push(delimiter);
+    }
+    group.push_str(segment);
+    group.push(delimiter);
+    groups.push(group.clone());
+  }
+  groups
+}
+
+/// A single entry of a listing as it's serialized, the `type` field tells the kinds apart
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ListEntry {
+  Bucket {
+    name: String,
+    creation_date: Option<String>,
+  },
+  /// The totals are only known when every object under the directory was listed
+  Directory {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    objects: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+  },
+  Object {
+    key: String,
+    size: i64,
+    last_modified: Option<String>,
+    e_tag: Option<String>,
+    storage_class: Option<String>,
+  },
+  Prefix {
+    prefix: String,
+  },
+}
+
+impl ListEntry {
+  pub fn from_bucket(bucket: &aws_sdk_s3::types::Bucket) -> Self {
+    ListEntry::Bucket {
+      name: bucket.name().unwrap_or_default().to_string(),
+      creation_date: bucket.creation_date().map(|d| utc_datetime(*d)),
+    }
+  }
+
+  pub fn from_directory(directory: &S3Directory) -> Self {
+    ListEntry::Directory {
+      name: directory.name.clone(),
+      size: Some(directory.size()),
+      objects: Some(directory.len()),
+      last_modified: Some(utc_datetime(directory.last_modified())),
+    }
+  }
+
+  /// A directory known only by its name, e.g. from the common prefixes of a listing
+  pub fn from_directory_name(name: &str) -> Self {
+    ListEntry::Directory {
+      name: name.to_string(),
+      size: None,
+      objects: None,
+      last_modified: None,
+    }
+  }
+
+  pub fn from_object(object: &aws_sdk_s3::types::Object) -> Self {
+    ListEntry::Object {
+      key: object.key().unwrap_or_default().to_string(),
+      size: object.size(),
+      last_modified: object.last_modified().map(|d| utc_datetime(*d)),
+      e_tag: object.e_tag().map(|t| t.trim_matches('"').to_string()),
+      storage_class: object.storage_class().map(|c| c.as_str().to_string()),
+    }
+  }
+
+  pub fn from_prefix(prefix: &str) -> Self {
+    ListEntry::Prefix {
+      prefix: prefix.to_string(),
+    }
+  }
+
+  /// Row of the entry in the order of [LIST_COLUMNS], values that don't apply are left empty
+  pub fn row(&self, human_size: bool) -> Vec<String> {
+    let (date, kind, size, key, storage_class) = match self {
+      ListEntry::Bucket { name, creation_date } => {
+        (creation_date.clone(), "BUCKET", None, name.clone(), None)
+      }
+      ListEntry::Directory { name, size, last_modified, .. } => {
+        (last_modified.clone(), "DIR", *size, name.clone(), None)
+      }
+      ListEntry::Object { key, size, last_modified, storage_class, .. } => {
+        (last_modified.clone(), "OBJ", Some(*size as usize), key.clone(), storage_class.clone())
+      }
+      ListEntry::Prefix { prefix } => (None, "PREFIX", None, prefix.clone(), None),
+    };
+
+    vec![
+      date.unwrap_or_default(),
+      kind.to_string(),
+      size.map(|s| format_size(s, human_size)).unwrap_or_default(),
+      key,
+      storage_class.unwrap_or_default(),
+    ]
+  }
+}
+
+/// Formats a size in bytes, or in the largest unit that keeps it above 1 when `human_size`
+pub fn format_size(size: usize, human_size: bool) -> String {
+  match human_size {
+    true => human_bytes::human_bytes(size as f64),
+    false => size.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod output_tests {
+  use aws_sdk_s3::primitives::DateTime;
+  use aws_sdk_s3::types::{Object, ObjectStorageClass};
+
+  use crate::s3::content::S3File;
+
+  use super::*;
+
+  #[test]
+  fn test_serialize_entries() {
+    let object = Object::builder()
+       .key("logs/app.log")
+       .size(42)
+       .e_tag("\"5d41402abc4b2a76b9719d911017c592\"")
+       .last_modified(DateTime::from_secs(1696118400))
+       .storage_class(ObjectStorageClass::Standard)
+       .build();
+
+    assert_eq!(
+      serde_json::to_string(&ListEntry::from_object(&object)).unwrap(),
+      r#"{"type":"object","key":"logs/app.log","size":42,"last_modified":"2023-10-01T00:00:00Z","e_tag":"5d41402abc4b2a76b9719d911017c592","storage_class":"STANDARD"}"#
+    );
+
+    let mut directory = S3Directory::new(String::from("logs"));
+    directory.add_file(S3File {
+      last_modified: DateTime::from_secs(1696118400),
+      size: 42,
+      key: String::from("logs/app.log"),
+    });
+
+    assert_eq!(
+      serde_json::to_string(&ListEntry::from_directory(&directory)).unwrap(),
+      r#"{"type":"directory","name":"logs","size":42,"objects":1,"last_modified":"2023-10-01T00:00:00Z"}"#
+    );
+
+    assert_eq!(
+      ListEntry::from_object(&object).row(false),
+      vec!["2023-10-01T00:00:00Z", "OBJ", "42", "logs/app.log", "STANDARD"]
+    );
+    assert_eq!(
+      ListEntry::from_directory(&directory).row(true),
+      vec!["2023-10-01T00:00:00Z", "DIR", "42 B", "logs", ""]
+    );
+
+    assert_eq!(
+      serde_json::to_string(&ListEntry::from_directory_name("logs")).unwrap(),
+      r#"{"type":"directory","name":"logs"}"#
+    );
+    assert_eq!(ListEntry::from_directory_name("logs").row(false), vec!["", "DIR", "", "logs", ""]);
+
+    assert_eq!(
+      serde_json::to_string(&DuOutput::new(2048, 3)).unwrap(),
+      r#"{"total_size_bytes":2048,"total_size_human":"2 KB","total_objects":3,"prefixes":[]}"#
+    );
+
+    // logs/2023/ includes the 1024 bytes of logs/2023/01/
+    let prefixes = BTreeMap::from([
+      (String::from("logs/2023/"), (1536, 2)),
+      (String::from("logs/2023/01/"), (1024, 1)),
+    ]);
+    assert_eq!(
+      serde_json::to_string(&DuOutput::new(2048, 3).with_prefixes(prefixes).prefixes).unwrap(),
+      concat!(
+        r#"[{"prefix":"logs/2023/","total_size_bytes":1536,"total_size_human":"1.5 KB","total_objects":2},"#,
+        r#"{"prefix":"logs/2023/01/","total_size_bytes":1024,"total_size_human":"1 KB","total_objects":1}]"#
+      )
+    );
+  }
+
+  #[test]
+  fn test_du_groups() {
+    assert_eq!(du_groups("logs", "logs/2023/01/app.log", '/', 1), vec!["logs/2023/"]);
+    assert_eq!(du_groups("logs", "logs/2023/01/app.log", '/', 2), vec!["logs/2023/", "logs/2023/01/"]);
+    assert_eq!(du_groups("logs", "logs/2023/01/app.log", '/', 5), vec!["logs/2023/", "logs/2023/01/"]);
+    assert_eq!(du_groups("logs/", "logs/2023/app.log", '/', 1), vec!["logs/2023/"]);
+    assert_eq!(du_groups("", "a/b/c.txt", '/', 1), vec!["a/"]);
+
+    // Objects right under the listed prefix are only part of the total
+    assert!(du_groups("logs", "logs/app.log", '/', 1).is_empty());
+    assert!(du_groups("", "c.txt", '/', 1).is_empty());
+  }
+}
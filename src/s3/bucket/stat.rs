@@ -0,0 +1,117 @@
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+
+use crate::commands::stat::StatOpts;
+use crate::s3::bucket::Bucket;
+use crate::s3::bucket::transfer::relative_key;
+use crate::s3::ParsedS3Url;
+use crate::utc_datetime;
+
+impl Bucket {
+  /// Prints the metadata of an object, or of every object under a prefix when recursive
+  pub async fn stat(&self, opts: StatOpts) -> anyhow::Result<()> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let prefix = parsed.segments.join(opts.delimiter.to_string().as_str());
+
+    let keys = if opts.recursive {
+      self.list_objects(&opts.path, &opts.delimiter)
+         .await?
+         .into_iter()
+         .filter_map(|object| object.key)
+         .filter(|key| relative_key(key, &prefix, opts.delimiter, &opts.exclude).is_some())
+         .collect::<Vec<_>>()
+    } else {
+      if prefix.is_empty() {
+        return Err(anyhow::anyhow!("An object key is required, use --recursive to stat a whole prefix"));
+      }
+      vec![prefix]
+    };
+
+    for (index, key) in keys.iter().enumerate() {
+      let head = self.client
+         .head_object()
+         .bucket(&parsed.bucket_name)
+         .key(key)
+         .send()
+         .await?;
+
+      if index > 0 {
+        println!();
+      }
+
+      println!("s3://{}/{}", parsed.bucket_name, key);
+      for (name, value) in stat_fields(&head) {
+        println!("  {:<22}{}", format!("{}:", name), value);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Describes the metadata of an object as (name, value) pairs, headers that weren't set are left out
+fn stat_fields(head: &HeadObjectOutput) -> Vec<(String, String)> {
+  let size = head.content_length();
+  let mut fields = vec![
+    (String::from("Size"), format!("{} ({})", size, human_bytes::human_bytes(size as f64))),
+  ];
+
+  let mut push = |name: &str, value: Option<String>| {
+    if let Some(value) = value {
+      fields.push((name.to_string(), value));
+    }
+  };
+
+  push("ETag", head.e_tag().map(|t| t.to_string()));
+  push("Content-Type", head.content_type().map(|t| t.to_string()));
+  push("Content-Encoding", head.content_encoding().map(|t| t.to_string()));
+  push("Content-Disposition", head.content_disposition().map(|t| t.to_string()));
+  push("Cache-Control", head.cache_control().map(|t| t.to_string()));
+  push("Expires", head.expires().map(|t| utc_datetime(*t)));
+  push("Last-Modified", head.last_modified().map(|t| utc_datetime(*t)));
+  // Standard objects don't report a storage class
+  push("Storage-Class", Some(head.storage_class().map(|c| c.as_str()).unwrap_or("STANDARD").to_string()));
+  push("Encryption", head.server_side_encryption().map(|e| e.as_str().to_string()));
+  push("KMS-Key-Id", head.ssekms_key_id().map(|k| k.to_string()));
+  push("Version-Id", head.version_id().map(|v| v.to_string()));
+
+  if let Some(metadata) = head.metadata() {
+    let mut metadata = metadata.iter().collect::<Vec<_>>();
+    metadata.sort();
+    for (name, value) in metadata {
+      fields.push((format!("x-amz-meta-{}", name), value.to_string()));
+    }
+  }
+
+  fields
+}
+
+#[cfg(test)]
+mod stat_tests {
+  use aws_sdk_s3::primitives::DateTime;
+  use aws_sdk_s3::types::ServerSideEncryption;
+
+  use super::*;
+
+  #[test]
+  fn test_stat_fields() {
+    let head = HeadObjectOutput::builder()
+       .content_length(2048)
+       .e_tag("\"5d41402abc4b2a76b9719d911017c592\"")
+       .content_type("text/plain")
+       .last_modified(DateTime::from_secs(1696118400))
+       .server_side_encryption(ServerSideEncryption::Aes256)
+       .metadata("sha256", "abc")
+       .build();
+
+    let fields = stat_fields(&head);
+    let value = |name: &str| fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+
+    assert_eq!(value("Size"), Some("2048 (2 KB)"));
+    assert_eq!(value("Content-Type"), Some("text/plain"));
+    assert_eq!(value("Last-Modified"), Some("2023-10-01T00:00:00Z"));
+    assert_eq!(value("Storage-Class"), Some("STANDARD"));
+    assert_eq!(value("Encryption"), Some("AES256"));
+    assert_eq!(value("x-amz-meta-sha256"), Some("abc"));
+    assert_eq!(value("Cache-Control"), None);
+  }
+}
@@ -0,0 +1,271 @@
+use aws_sdk_s3::Client;
+use colored::Colorize;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::CompletedPart;
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::commands::copy::CopyOpts;
+use crate::s3::bucket::{Bucket, SPARKLE};
+use crate::s3::bucket::checksum::SHA256_METADATA_KEY;
+use crate::s3::bucket::multipart::{completed_part, MAX_PART_SIZE, MAX_PARTS};
+use crate::s3::bucket::transfer::{object_acl, print_line, TransferOpts};
+use crate::s3::ParsedS3Url;
+
+/// The `FROM` argument that reads the contents from stdin
+pub const STDIN_PATH: &str = "-";
+
+/// Number of parts of a stream sent with the same part size before it's doubled
+const PARTS_PER_SIZE: u64 = 1000;
+
+impl Bucket {
+  /// Uploads everything read from stdin to the destination key. The size is unknown up front, so
+  /// stdin is read in chunks of a growing part size and sent as a multipart upload. Input that
+  /// fits in a single part is sent with PutObject along with the SHA-256 of the contents.
+  ///
+  /// The digest of a multipart upload is only known once the stream ended, with `--checksum` it's
+  /// added to the metadata afterwards by copying the completed object onto itself
+  pub(crate) async fn upload_stdin(&self, opts: &CopyOpts) -> anyhow::Result<()> {
+    if opts.recursive {
+      return Err(anyhow::anyhow!("Stdin can't be copied recursively"));
+    }
+
+    if opts.remove_source {
+      return Err(anyhow::anyhow!("Stdin can't be moved, use cp instead"));
+    }
+
+    let destination = ParsedS3Url::parse_from(&opts.to, &opts.delimiter)?;
+    if destination.segments.is_empty() || opts.to.ends_with(opts.delimiter) {
+      return Err(anyhow::anyhow!("An object key is required when copying from stdin"));
+    }
+
+    let key = destination.segments.join(opts.delimiter.to_string().as_str());
+    let transfer = opts.transfer();
+
+    let started = Instant::now();
+    let pb = stream_progress_bar(opts.show_progress);
+
+    let mut stdin = tokio::io::stdin();
+    let first = read_chunk(&mut stdin, transfer.part_size).await?;
+
+    if (first.len() as u64) < transfer.part_size {
+      let length = first.len() as u64;
+      self.client
+         .put_object()
+         .bucket(&destination.bucket_name)
+         .key(&key)
+         .metadata(SHA256_METADATA_KEY, format!("{:x}", Sha256::digest(&first)))
+         .set_acl(transfer.acl.map(object_acl))
+         .content_length(length as i64)
+         .body(ByteStream::from(first))
+         .send()
+         .await?;
+
+      if !pb.is_finished() {
+        pb.inc(length);
+      }
+    } else {
+      let (size, sha256) = self.upload_stream(&mut stdin, first, &destination.bucket_name, &key, &transfer, &pb).await?;
+
+      // The upload itself succeeded, only the digest is missing when storing it fails
+      if opts.checksum {
+        if let Err(e) = self.store_sha256(&destination.bucket_name, &key, size as i64, &sha256, transfer.acl).await {
+          pb.suspend(|| eprintln!(
+            "{} Couldn't store the checksum of s3://{}/{}: {}",
+            "warn:".yellow(), destination.bucket_name, key, e
+          ));
+        }
+      }
+    }
+
+    print_line(&pb, format!("upload: {} to s3://{}/{}", STDIN_PATH, destination.bucket_name, key));
+
+    if !pb.is_finished() {
+      pb.finish_and_clear();
+      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+    }
+
+    Ok(())
+  }
+
+  /// Sends a reader of unknown length as a multipart upload, starting with an already read chunk.
+  /// At most `concurrency` chunks are in flight, the next one is only read once a slot is free.
+  /// The upload is aborted on failure since a stream can't be resumed. Returns the size and the
+  /// SHA-256 of everything that was read
+  async fn upload_stream<R: AsyncRead + Unpin>(
+    &self,
+    reader: &mut R,
+    first: Vec<u8>,
+    bucket: &str,
+    key: &str,
+    transfer: &TransferOpts,
+    pb: &ProgressBar,
+  ) -> anyhow::Result<(u64, String)> {
+    let upload = self.client
+       .create_multipart_upload()
+       .bucket(bucket)
+       .key(key)
+       .set_acl(transfer.acl.map(object_acl))
+       .send()
+       .await?;
+
+    let upload_id = match upload.upload_id() {
+      Some(id) => id.to_string(),
+      None => return Err(anyhow::anyhow!("No upload id was returned for {}", key)),
+    };
+
+    let result = async {
+      let mut tasks: JoinSet<anyhow::Result<(CompletedPart, u64)>> = JoinSet::new();
+      let mut parts: Vec<CompletedPart> = Vec::new();
+      let mut hasher = Sha256::new();
+      let mut size: u64 = 0;
+      let mut chunk = first;
+      let mut part_number: i32 = 1;
+
+      while !chunk.is_empty() {
+        if part_number as u64 > MAX_PARTS {
+          return Err(anyhow::anyhow!(
+            "Stdin doesn't fit in {} parts, use a larger --part-size",
+            MAX_PARTS
+          ));
+        }
+
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
+
+        if tasks.len() >= transfer.concurrency.max(1) {
+          if let Some(joined) = tasks.join_next().await {
+            parts.push(finish_chunk(joined??, pb));
+          }
+        }
+
+        let upload = ChunkUpload {
+          client: self.client.clone(),
+          bucket: bucket.to_string(),
+          key: key.to_string(),
+          upload_id: upload_id.clone(),
+          part_number,
+          chunk,
+        };
+        tasks.spawn(async move { upload.send().await });
+
+        part_number += 1;
+        chunk = read_chunk(reader, stream_part_size(transfer.part_size, part_number as u64)).await?;
+      }
+
+      while let Some(joined) = tasks.join_next().await {
+        parts.push(finish_chunk(joined??, pb));
+      }
+
+      parts.sort_by_key(|p| p.part_number());
+      self.complete_multipart(bucket, key, &upload_id, parts).await?;
+
+      anyhow::Ok((size, format!("{:x}", hasher.finalize())))
+    }.await;
+
+    if result.is_err() {
+      self.abort_multipart(bucket, key, &upload_id).await;
+    }
+
+    result
+  }
+}
+
+/// A chunk of a stream, owned so it can be sent from a spawned task
+struct ChunkUpload {
+  client: Client,
+  bucket: String,
+  key: String,
+  upload_id: String,
+  part_number: i32,
+  chunk: Vec<u8>,
+}
+
+impl ChunkUpload {
+  async fn send(self) -> anyhow::Result<(CompletedPart, u64)> {
+    let size = self.chunk.len() as u64;
+
+    let response = self.client
+       .upload_part()
+       .bucket(&self.bucket)
+       .key(&self.key)
+       .upload_id(&self.upload_id)
+       .part_number(self.part_number)
+       .content_length(size as i64)
+       .body(ByteStream::from(self.chunk))
+       .send()
+       .await?;
+
+    let part = completed_part(self.part_number, response.e_tag().map(|t| t.to_string()));
+
+    Ok((part, size))
+  }
+}
+
+fn finish_chunk((part, size): (CompletedPart, u64), pb: &ProgressBar) -> CompletedPart {
+  if !pb.is_finished() {
+    pb.inc(size);
+  }
+  part
+}
+
+/// Size of the given part of a stream. The size doubles every [PARTS_PER_SIZE] parts, up to
+/// [MAX_PART_SIZE], so a stream of any size S3 accepts fits in [MAX_PARTS] parts while short
+/// streams keep small parts. Only `concurrency` parts are held in memory at once
+fn stream_part_size(part_size: u64, part_number: u64) -> u64 {
+  let doublings = ((part_number.max(1) - 1) / PARTS_PER_SIZE).min(32) as u32;
+  part_size.saturating_mul(1 << doublings).min(MAX_PART_SIZE)
+}
+
+/// Reads until the chunk is full or the reader is exhausted, an empty chunk means the end
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, size: u64) -> std::io::Result<Vec<u8>> {
+  let mut chunk = Vec::with_capacity(size as usize);
+  reader.take(size).read_to_end(&mut chunk).await?;
+  Ok(chunk)
+}
+
+/// A progress bar without a total, it only shows how much has been sent
+fn stream_progress_bar(show_progress: bool) -> ProgressBar {
+  let pb = ProgressBar::new_spinner();
+  let style = ProgressStyle::with_template("{spinner} [{elapsed_precise}] {bytes} ({bytes_per_sec})")
+     .unwrap()
+     .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ");
+  pb.set_style(style);
+
+  if !show_progress {
+    pb.finish_and_clear();
+  }
+
+  pb
+}
+
+#[cfg(test)]
+mod stdin_tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn test_read_chunk() {
+    let mut reader: &[u8] = b"0123456789";
+    assert_eq!(read_chunk(&mut reader, 4).await.unwrap(), b"0123");
+    assert_eq!(read_chunk(&mut reader, 4).await.unwrap(), b"4567");
+    assert_eq!(read_chunk(&mut reader, 4).await.unwrap(), b"89");
+    assert!(read_chunk(&mut reader, 4).await.unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_stream_part_size() {
+    let part_size = 8 * 1024 * 1024;
+    assert_eq!(stream_part_size(part_size, 1), part_size);
+    assert_eq!(stream_part_size(part_size, 1000), part_size);
+    assert_eq!(stream_part_size(part_size, 1001), part_size * 2);
+    assert_eq!(stream_part_size(part_size, 9001), part_size * 512);
+    assert_eq!(stream_part_size(MAX_PART_SIZE, 5000), MAX_PART_SIZE);
+
+    // The parts add up to more than the 5 TiB S3 allows for an object
+    let total: u64 = (1..=MAX_PARTS).map(|n| stream_part_size(part_size, n)).sum();
+    assert!(total > 5 * 1024 * 1024 * 1024 * 1024);
+  }
+}
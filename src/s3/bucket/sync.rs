@@ -0,0 +1,441 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use colored::Colorize;
+use indicatif::HumanDuration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+
+use crate::commands::sync::SyncOpts;
+use crate::confirm;
+use crate::fs::list_directory_files;
+use crate::s3::bucket::{Bucket, SPARKLE};
+use crate::s3::bucket::checksum::matches_checksum;
+use crate::s3::bucket::transfer::{is_excluded, join_key, local_path_for, print_line, relative_key, transfer_progress_bar};
+use crate::s3::ParsedS3Url;
+
+/// A file or an object taking part in a sync
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncEntry {
+  pub size: u64,
+  /// Last modification time, in seconds since the unix epoch
+  pub modified: i64,
+}
+
+impl Bucket {
+  /// Mirrors the source into the destination, only files that are new or changed are transferred
+  pub async fn sync(&self, opts: SyncOpts) -> anyhow::Result<()> {
+    let from_s3 = ParsedS3Url::is_s3url(&opts.source);
+    let to_s3 = ParsedS3Url::is_s3url(&opts.destination);
+
+    match (from_s3, to_s3) {
+      (false, true) => self.sync_up(&opts).await,
+      (true, false) => self.sync_down(&opts).await,
+      _ => Err(anyhow::anyhow!("Syncing from {} to {} is not supported", &opts.source, &opts.destination)),
+    }
+  }
+
+  /// Uploads local files that are missing or changed on the remote
+  async fn sync_up(&self, opts: &SyncOpts) -> anyhow::Result<()> {
+    let destination = ParsedS3Url::parse_from(&opts.destination, &opts.delimiter)?;
+    let source = local_entries(&opts.source, opts.delimiter, &opts.exclude)?;
+    let remote = self.remote_entries(&opts.destination, opts.delimiter, &opts.exclude).await?;
+
+    let verified = match opts.checksum {
+      true => Some(self.verify_checksums(&destination, &opts.source, &source, &remote, opts).await),
+      false => None,
+    };
+
+    let transfers = plan_transfers(&source, &remote, verified.as_ref());
+    let deletions = match opts.delete {
+      true => plan_deletions(&source, &remote),
+      false => Vec::new(),
+    };
+
+    if !confirm_deletions(opts, deletions.len())? {
+      return Ok(());
+    }
+
+    let started = Instant::now();
+    let pb = transfer_progress_bar(
+      transfers.iter().map(|r| source[r].size).sum(),
+      opts.show_progress && !opts.dry_run,
+    );
+
+    for relative in &transfers {
+      let path = local_path_for(Path::new(&opts.source), relative, opts.delimiter);
+      let key = join_key(&destination.segments, relative, opts.delimiter);
+
+      let line = format!("upload: {} to s3://{}/{}", path.display(), destination.bucket_name, key);
+      if opts.dry_run {
+        println!("(dryrun) {}", line);
+        continue;
+      }
+
+      // The digest stored with the object is only computed for the files that are uploaded
+      let entry = &source[relative];
+      let sha256 = sha256::try_digest(path.as_path())?;
+      self.upload_file(
+        &path,
+        entry.size,
+        &sha256,
+        &destination.bucket_name,
+        &key,
+        &opts.transfer(),
+        &pb,
+      ).await?;
+
+      print_line(&pb, line);
+    }
+
+    let keys = deletions
+       .iter()
+       .map(|relative| join_key(&destination.segments, relative, opts.delimiter))
+       .collect::<Vec<_>>();
+
+    if opts.dry_run {
+      for key in &keys {
+        println!("(dryrun) delete: s3://{}/{}", destination.bucket_name, key);
+      }
+    } else if !keys.is_empty() {
+      let failures = self.delete_objects(&destination.bucket_name, &keys).await?;
+      for key in keys.iter().filter(|k| !failures.iter().any(|f| &f.key == *k)) {
+        print_line(&pb, format!("delete: s3://{}/{}", destination.bucket_name, key));
+      }
+      for failure in &failures {
+        eprintln!(
+          "{} delete failed: s3://{}/{} ({}: {})",
+          "error:".red(), destination.bucket_name, failure.key, failure.code, failure.message
+        );
+      }
+      if !failures.is_empty() {
+        return Err(anyhow::anyhow!("{} objects could not be deleted", failures.len()));
+      }
+    }
+
+    if !pb.is_finished() {
+      pb.finish_and_clear();
+      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+    }
+
+    Ok(())
+  }
+
+  /// Downloads objects that are missing or changed on local, downloaded files get the last
+  /// modified time of their object so the next sync sees them as unchanged
+  async fn sync_down(&self, opts: &SyncOpts) -> anyhow::Result<()> {
+    let source = ParsedS3Url::parse_from(&opts.source, &opts.delimiter)?;
+    let remote = self.remote_entries(&opts.source, opts.delimiter, &opts.exclude).await?;
+    // The destination is created by the sync when it doesn't exist yet
+    let local = match Path::new(&opts.destination).exists() {
+      true => local_entries(&opts.destination, opts.delimiter, &opts.exclude)?,
+      false => BTreeMap::new(),
+    };
+
+    let verified = match opts.checksum {
+      true => Some(self.verify_checksums(&source, &opts.destination, &local, &remote, opts).await),
+      false => None,
+    };
+
+    let transfers = plan_transfers(&remote, &local, verified.as_ref());
+    let deletions = match opts.delete {
+      true => plan_deletions(&remote, &local),
+      false => Vec::new(),
+    };
+
+    if !confirm_deletions(opts, deletions.len())? {
+      return Ok(());
+    }
+
+    let started = Instant::now();
+    let pb = transfer_progress_bar(
+      transfers.iter().map(|r| remote[r].size).sum(),
+      opts.show_progress && !opts.dry_run,
+    );
+
+    for relative in &transfers {
+      let key = join_key(&source.segments, relative, opts.delimiter);
+      let path = local_path_for(Path::new(&opts.destination), relative, opts.delimiter);
+      let entry = &remote[relative];
+
+      let line = format!("download: s3://{}/{} to {}", source.bucket_name, key, path.display());
+      if opts.dry_run {
+        println!("(dryrun) {}", line);
+        continue;
+      }
+
+      self.download_object(&source.bucket_name, &key, entry.size, &path, &opts.transfer(), &pb).await?;
+
+      let modified = UNIX_EPOCH + Duration::from_secs(entry.modified.max(0) as u64);
+      std::fs::File::options().write(true).open(&path)?.set_modified(modified)?;
+
+      print_line(&pb, line);
+    }
+
+    for relative in &deletions {
+      let path = local_path_for(Path::new(&opts.destination), relative, opts.delimiter);
+      if opts.dry_run {
+        println!("(dryrun) delete: {}", path.display());
+        continue;
+      }
+
+      std::fs::remove_file(&path)?;
+      print_line(&pb, format!("delete: {}", path.display()));
+    }
+
+    if !pb.is_finished() {
+      pb.finish_and_clear();
+      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+    }
+
+    Ok(())
+  }
+
+  /// Lists every object under the url, keyed by the key relative to the url
+  async fn remote_entries(
+    &self,
+    url: &String,
+    delimiter: char,
+    exclude: &[regex::Regex],
+  ) -> anyhow::Result<BTreeMap<String, SyncEntry>> {
+    let parsed = ParsedS3Url::parse_from(url, &delimiter)?;
+    let prefix = parsed.segments.join(delimiter.to_string().as_str());
+
+    let mut entries = BTreeMap::new();
+    let mut pages = self.object_pages(url, &delimiter)?;
+    while let Some(page) = pages.next().await {
+      for object in page? {
+        let relative = match relative_key(object.key().unwrap_or_default(), &prefix, delimiter, exclude) {
+          // Keys ending with the delimiter are directory placeholders
+          Some(relative) if !relative.is_empty() && !relative.ends_with(delimiter) => relative,
+          _ => continue,
+        };
+
+        entries.insert(relative.to_string(), SyncEntry {
+          size: object.size().max(0) as u64,
+          modified: object.last_modified().map(|d| d.secs()).unwrap_or_default(),
+        });
+      }
+    }
+
+    Ok(entries)
+  }
+
+  /// Returns the relative paths present on both sides whose contents are the same, every object
+  /// with the same size as its local counterpart is compared by checksum. Only those local files
+  /// are read to compute their digest
+  async fn verify_checksums(
+    &self,
+    remote_url: &ParsedS3Url,
+    local_root: &str,
+    local: &BTreeMap<String, SyncEntry>,
+    remote: &BTreeMap<String, SyncEntry>,
+    opts: &SyncOpts,
+  ) -> BTreeSet<String> {
+    let mut tasks: JoinSet<Option<String>> = JoinSet::new();
+    let mut verified: BTreeSet<String> = BTreeSet::new();
+
+    for (relative, entry) in local {
+      match remote.get(relative) {
+        Some(object) if object.size == entry.size => {}
+        _ => continue,
+      }
+
+      if tasks.len() >= opts.concurrency.max(1) {
+        if let Some(Ok(Some(relative))) = tasks.join_next().await {
+          verified.insert(relative);
+        }
+      }
+
+      let client = self.client.clone();
+      let bucket = remote_url.bucket_name.clone();
+      let key = join_key(&remote_url.segments, relative, opts.delimiter);
+      let path = local_path_for(Path::new(local_root), relative, opts.delimiter);
+      let relative = relative.clone();
+
+      tasks.spawn(async move {
+        let sha256 = sha256::try_digest(path.as_path()).ok()?;
+        matches_checksum(client, bucket, key, path, sha256).await.then_some(relative)
+      });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+      if let Ok(Some(relative)) = joined {
+        verified.insert(relative);
+      }
+    }
+
+    verified
+  }
+}
+
+/// Lists every file under the local root, keyed by the path relative to the root with its
+/// components joined by the delimiter. A root that doesn't exist is an error, a mistyped source
+/// must never look like an empty one that everything is deleted to match
+fn local_entries(
+  root: &str,
+  delimiter: char,
+  exclude: &[regex::Regex],
+) -> anyhow::Result<BTreeMap<String, SyncEntry>> {
+  if !Path::new(root).exists() {
+    return Err(anyhow::anyhow!("{} does not exist", root));
+  }
+
+  let mut entries = BTreeMap::new();
+
+  for path in list_directory_files(root, true)? {
+    let relative = match path.strip_prefix(root) {
+      Ok(p) => p.components()
+         .map(|c| c.as_os_str().to_string_lossy().to_string())
+         .collect::<Vec<_>>()
+         .join(delimiter.to_string().as_str()),
+      Err(_) => continue,
+    };
+
+    if relative.is_empty() || is_excluded(exclude, &relative) {
+      continue;
+    }
+
+    let metadata = std::fs::metadata(&path)?;
+    entries.insert(relative, SyncEntry {
+      size: metadata.len(),
+      modified: unix_seconds(metadata.modified()?),
+    });
+  }
+
+  Ok(entries)
+}
+
+/// Asks before deleting anything unless `--force` is given, nothing is asked on a dry run
+fn confirm_deletions(opts: &SyncOpts, count: usize) -> anyhow::Result<bool> {
+  if count == 0 || opts.force || opts.dry_run {
+    return Ok(true);
+  }
+
+  let proceed = confirm(&format!(
+    "{} files in {} have no counterpart in the source and will be deleted, do you want to continue?",
+    count,
+    opts.destination.bold()
+  ))?;
+
+  if !proceed {
+    println!("{}: Nothing was synced", "!!".red());
+  }
+
+  Ok(proceed)
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+  match time.duration_since(UNIX_EPOCH) {
+    Ok(d) => d.as_secs() as i64,
+    Err(_) => 0,
+  }
+}
+
+/// Returns the entries of the source that have to be transferred, those missing from the
+/// destination, with a different size, or modified after their destination counterpart.
+/// With `verified` (the entries known to have the same checksum on both sides) the
+/// modification time is ignored and every entry that wasn't verified is transferred
+pub fn plan_transfers(
+  source: &BTreeMap<String, SyncEntry>,
+  destination: &BTreeMap<String, SyncEntry>,
+  verified: Option<&BTreeSet<String>>,
+) -> Vec<String> {
+  source
+     .iter()
+     .filter(|(relative, entry)| match destination.get(*relative) {
+       None => true,
+       Some(existing) if entry.size != existing.size => true,
+       Some(existing) => match verified {
+         Some(verified) => !verified.contains(*relative),
+         None => entry.modified > existing.modified,
+       },
+     })
+     .map(|(relative, _)| relative.clone())
+     .collect()
+}
+
+/// Returns the entries of the destination that have no counterpart in the source
+pub fn plan_deletions(
+  source: &BTreeMap<String, SyncEntry>,
+  destination: &BTreeMap<String, SyncEntry>,
+) -> Vec<String> {
+  destination
+     .keys()
+     .filter(|relative| !source.contains_key(*relative))
+     .cloned()
+     .collect()
+}
+
+#[cfg(test)]
+mod sync_tests {
+  use super::*;
+
+  fn entry(size: u64, modified: i64) -> SyncEntry {
+    SyncEntry { size, modified }
+  }
+
+  #[test]
+  fn test_plan_transfers() {
+    let mut source = BTreeMap::new();
+    source.insert(String::from("new.txt"), entry(10, 100));
+    source.insert(String::from("resized.txt"), entry(20, 100));
+    source.insert(String::from("touched.txt"), entry(30, 200));
+    source.insert(String::from("same.txt"), entry(40, 100));
+    source.insert(String::from("older.txt"), entry(50, 100));
+
+    let mut destination = BTreeMap::new();
+    destination.insert(String::from("resized.txt"), entry(25, 100));
+    destination.insert(String::from("touched.txt"), entry(30, 150));
+    destination.insert(String::from("same.txt"), entry(40, 100));
+    destination.insert(String::from("older.txt"), entry(50, 300));
+    destination.insert(String::from("extra.txt"), entry(60, 100));
+
+    assert_eq!(plan_transfers(&source, &destination, None), vec!["new.txt", "resized.txt", "touched.txt"]);
+    assert_eq!(plan_deletions(&source, &destination), vec!["extra.txt"]);
+
+    // Same length rewrites are only caught by checksum, while touched files are left alone
+    let verified = BTreeSet::from([String::from("touched.txt"), String::from("older.txt")]);
+    assert_eq!(plan_transfers(&source, &destination, Some(&verified)), vec!["new.txt", "resized.txt", "same.txt"]);
+  }
+
+  #[test]
+  fn test_local_entries() {
+    let entries = local_entries("src", '/', &[regex::Regex::new(r"^commands/").unwrap()]).unwrap();
+    assert!(entries.contains_key("s3/bucket/sync.rs"));
+    assert!(!entries.contains_key("commands/sync.rs"));
+
+    assert!(local_entries("does-not-exist", '/', &[]).is_err());
+  }
+
+  #[tokio::test]
+  async fn test_sync_up_missing_source() {
+    let bucket = Bucket::new(
+      String::from("http://localhost:9000"),
+      String::from("access-key"),
+      String::from("secret-key"),
+    );
+
+    let opts = SyncOpts {
+      verbose: false,
+      show_progress: false,
+      dry_run: false,
+      delete: true,
+      force: true,
+      checksum: false,
+      delimiter: '/',
+      source: String::from("does-not-exist"),
+      destination: String::from("s3://bucket/backup"),
+      exclude: Vec::new(),
+      part_size: crate::s3::bucket::multipart::DEFAULT_PART_SIZE,
+      concurrency: 1,
+      acl: None,
+    };
+
+    // Nothing is listed, let alone deleted, when the source is missing
+    let result = bucket.sync(opts).await;
+    assert_eq!(result.unwrap_err().to_string(), "does-not-exist does not exist");
+  }
+}
@@ -0,0 +1,722 @@
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedPart, MetadataDirective, ObjectCannedAcl};
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use tokio::time::Instant;
+
+use crate::commands::copy::CopyOpts;
+use crate::fs::{ClarifiedFile, list_directory_content, temp_path_for};
+use crate::s3::bucket::{Bucket, SPARKLE};
+use crate::s3::bucket::checksum::{matches_checksum, SHA256_METADATA_KEY};
+use crate::s3::bucket::multipart::MAX_PARTS;
+use crate::s3::{ACL, ParsedS3Url};
+
+/// Largest object that can be copied with a single CopyObject request
+const MAX_COPY_OBJECT_SIZE: i64 = 5 * 1024 * 1024 * 1024;
+
+/// Size of each part when an object is copied with UploadPartCopy
+const COPY_PART_SIZE: i64 = 512 * 1024 * 1024;
+
+/// Settings applied to every file of a transfer
+#[derive(Clone, Debug)]
+pub struct TransferOpts {
+  pub part_size: u64,
+  pub concurrency: usize,
+  /// Canned ACL applied to every uploaded or copied object
+  pub acl: Option<ACL>,
+}
+
+impl Bucket {
+  /// Uploads a local file or directory to the given S3 destination
+  pub(crate) async fn upload(&self, opts: &CopyOpts) -> anyhow::Result<()> {
+    let destination = ParsedS3Url::parse_from(&opts.to, &opts.delimiter)?;
+    let base = Path::new(&opts.from);
+    let single_file = base.is_file();
+
+    // When destination ends with a delimiter (or is the bucket root) files are placed under it,
+    // otherwise a single file is uploaded with exact destination key
+    let into_prefix = opts.to.ends_with(opts.delimiter) || destination.segments.is_empty();
+
+    let files = list_directory_content(&opts.from, opts.recursive)?
+       .into_iter()
+       .filter(|f| !is_excluded(&opts.exclude, &relative_path(base, f.path())))
+       .collect::<Vec<_>>();
+
+    let started = Instant::now();
+    let pb = transfer_progress_bar(
+      files.iter().map(|f| f.length() as u64).sum(),
+      opts.show_progress,
+    );
+
+    for file in &files {
+      let key = object_key_for(base, file.path(), &destination, single_file && !into_prefix, opts.delimiter);
+
+      let unchanged = opts.checksum && matches_checksum(
+        self.client.clone(),
+        destination.bucket_name.clone(),
+        key.clone(),
+        file.path().to_path_buf(),
+        file.sha256().to_string(),
+      ).await;
+
+      if unchanged {
+        if !pb.is_finished() {
+          pb.inc(file.length() as u64);
+        }
+      } else {
+        self.upload_file(
+          file.path(),
+          file.length() as u64,
+          file.sha256(),
+          &destination.bucket_name,
+          &key,
+          &opts.transfer(),
+          &pb,
+        ).await?;
+      }
+
+      if opts.remove_source {
+        self.verify_upload(&destination.bucket_name, &key, file.length() as u64, file.sha256()).await?;
+        tokio::fs::remove_file(file.path()).await?;
+      }
+
+      let line = match unchanged && !opts.remove_source {
+        true => format!("skip: {} is unchanged", file.path().display()),
+        false => format!(
+          "{}: {} to s3://{}/{}",
+          opts.action("upload"), file.path().display(), destination.bucket_name, key
+        ),
+      };
+      print_line(&pb, line);
+    }
+
+    if !pb.is_finished() {
+      pb.finish_and_clear();
+      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+    }
+
+    Ok(())
+  }
+
+  /// Uploads a single file, files larger than the part size are uploaded in parts. The SHA-256 of
+  /// the file is stored in the object metadata so later comparisons are exact
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) async fn upload_file(
+    &self,
+    path: &Path,
+    length: u64,
+    sha256: &str,
+    bucket: &str,
+    key: &str,
+    transfer: &TransferOpts,
+    pb: &ProgressBar,
+  ) -> anyhow::Result<()> {
+    if length > transfer.part_size {
+      return self.upload_multipart(path, sha256, bucket, key, transfer, pb).await;
+    }
+
+    let body = ByteStream::from_path(path).await?;
+    self.client
+       .put_object()
+       .bucket(bucket)
+       .key(key)
+       .metadata(SHA256_METADATA_KEY, sha256)
+       .set_acl(transfer.acl.map(object_acl))
+       .content_length(length as i64)
+       .body(body)
+       .send()
+       .await?;
+
+    if !pb.is_finished() {
+      pb.inc(length);
+    }
+
+    Ok(())
+  }
+
+  /// Downloads an object, or every object under a prefix when recursive, to the local filesystem
+  pub(crate) async fn download(&self, opts: &CopyOpts) -> anyhow::Result<()> {
+    let source = ParsedS3Url::parse_from(&opts.from, &opts.delimiter)?;
+    let prefix = source.segments.join(opts.delimiter.to_string().as_str());
+    let target = Path::new(&opts.to);
+
+    // (key, size, local path)
+    let mut downloads: Vec<(String, i64, PathBuf)> = Vec::new();
+
+    if opts.recursive {
+      for object in self.list_objects(&opts.from, &opts.delimiter).await? {
+        let key = object.key().unwrap_or_default();
+        let relative = match relative_key(key, &prefix, opts.delimiter, &opts.exclude) {
+          // Keys ending with the delimiter are directory placeholders
+          Some(relative) if !relative.is_empty() && !relative.ends_with(opts.delimiter) => relative,
+          _ => continue,
+        };
+
+        downloads.push((key.to_string(), object.size(), local_path_for(target, relative, opts.delimiter)));
+      }
+    } else {
+      if source.segments.is_empty() {
+        return Err(anyhow::anyhow!("An object key is required, use --recursive to download a whole prefix"));
+      }
+
+      let head = self.client
+         .head_object()
+         .bucket(&source.bucket_name)
+         .key(&prefix)
+         .send()
+         .await?;
+
+      let name = source.segments.last().unwrap();
+      let path = match target.is_dir() || opts.to.ends_with(std::path::MAIN_SEPARATOR) {
+        true => target.join(name),
+        false => target.to_path_buf(),
+      };
+      downloads.push((prefix.clone(), head.content_length(), path));
+    }
+
+    let started = Instant::now();
+    let pb = transfer_progress_bar(
+      downloads.iter().map(|(_, size, _)| *size as u64).sum(),
+      opts.show_progress,
+    );
+
+    for (key, size, path) in &downloads {
+      let unchanged = opts.checksum && path.is_file() && match ClarifiedFile::from_path(&path.to_string_lossy()) {
+        Ok(local) => matches_checksum(
+          self.client.clone(),
+          source.bucket_name.clone(),
+          key.clone(),
+          path.clone(),
+          local.sha256().to_string(),
+        ).await,
+        Err(_) => false,
+      };
+
+      if unchanged {
+        if !pb.is_finished() {
+          pb.inc(*size as u64);
+        }
+      } else {
+        self.download_object(&source.bucket_name, key, *size as u64, path, &opts.transfer(), &pb).await?;
+      }
+
+      if opts.remove_source {
+        self.verify_download(&source.bucket_name, key, path).await?;
+        self.delete_object(&source.bucket_name, key).await?;
+      }
+
+      let line = match unchanged && !opts.remove_source {
+        true => format!("skip: {} is unchanged", path.display()),
+        false => format!(
+          "{}: s3://{}/{} to {}",
+          opts.action("download"), source.bucket_name, key, path.display()
+        ),
+      };
+      print_line(&pb, line);
+    }
+
+    if !pb.is_finished() {
+      pb.finish_and_clear();
+      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+    }
+
+    Ok(())
+  }
+
+  /// Copies an object, or every object under a prefix when recursive, between buckets on the
+  /// server side so the bytes never pass through this machine
+  pub(crate) async fn copy_remote(&self, opts: &CopyOpts) -> anyhow::Result<()> {
+    let source = ParsedS3Url::parse_from(&opts.from, &opts.delimiter)?;
+    let destination = ParsedS3Url::parse_from(&opts.to, &opts.delimiter)?;
+    let delimiter = opts.delimiter.to_string();
+    let prefix = source.segments.join(delimiter.as_str());
+
+    // (source key, size, destination key)
+    let mut copies: Vec<(String, i64, String)> = Vec::new();
+
+    if opts.recursive {
+      check_recursive_destination(&source, &destination, opts.delimiter)?;
+
+      for object in self.list_objects(&opts.from, &opts.delimiter).await? {
+        let key = object.key().unwrap_or_default();
+        let relative = match relative_key(key, &prefix, opts.delimiter, &opts.exclude) {
+          Some(relative) if !relative.is_empty() => relative,
+          _ => continue,
+        };
+
+        copies.push((key.to_string(), object.size(), join_key(&destination.segments, relative, opts.delimiter)));
+      }
+    } else {
+      if source.segments.is_empty() {
+        return Err(anyhow::anyhow!("An object key is required, use --recursive to copy a whole prefix"));
+      }
+
+      let head = self.client
+         .head_object()
+         .bucket(&source.bucket_name)
+         .key(&prefix)
+         .send()
+         .await?;
+
+      let name = source.segments.last().unwrap();
+      let key = match opts.to.ends_with(opts.delimiter) || destination.segments.is_empty() {
+        true => join_key(&destination.segments, name, opts.delimiter),
+        false => destination.segments.join(delimiter.as_str()),
+      };
+      copies.push((prefix.clone(), head.content_length(), key));
+    }
+
+    // Checked before anything is copied, `mv` would otherwise delete the only copy
+    check_copies(&source.bucket_name, &destination.bucket_name, &copies)?;
+
+    let started = Instant::now();
+    let pb = transfer_progress_bar(
+      copies.iter().map(|(_, size, _)| *size as u64).sum(),
+      opts.show_progress,
+    );
+
+    for (key, size, destination_key) in &copies {
+      self.copy_object(&source.bucket_name, key, *size, &destination.bucket_name, destination_key, opts.acl).await?;
+
+      if opts.remove_source {
+        self.verify_copy(&source.bucket_name, key, &destination.bucket_name, destination_key).await?;
+        self.delete_object(&source.bucket_name, key).await?;
+      }
+
+      let line = format!(
+        "{}: s3://{}/{} to s3://{}/{}",
+        opts.action("copy"), source.bucket_name, key, destination.bucket_name, destination_key
+      );
+      if !pb.is_finished() {
+        pb.inc(*size as u64);
+      }
+      print_line(&pb, line);
+    }
+
+    if !pb.is_finished() {
+      pb.finish_and_clear();
+      println!("{} Done in {}", SPARKLE, HumanDuration(started.elapsed()));
+    }
+
+    Ok(())
+  }
+
+  /// Copies a single object with CopyObject, or with UploadPartCopy when it's over 5 GiB
+  pub(crate) async fn copy_object(
+    &self,
+    source_bucket: &str,
+    source_key: &str,
+    size: i64,
+    bucket: &str,
+    key: &str,
+    acl: Option<ACL>,
+  ) -> anyhow::Result<()> {
+    if size > MAX_COPY_OBJECT_SIZE {
+      return self.copy_object_multipart(source_bucket, source_key, size, bucket, key, acl, None).await;
+    }
+
+    self.client
+       .copy_object()
+       .copy_source(encode_copy_source(source_bucket, source_key))
+       .bucket(bucket)
+       .key(key)
+       .set_acl(acl.map(object_acl))
+       .send()
+       .await?;
+
+    Ok(())
+  }
+
+  /// Stores the SHA-256 of an object that was uploaded before its digest was known, by copying
+  /// the object onto itself with the digest added to its metadata
+  pub(crate) async fn store_sha256(
+    &self,
+    bucket: &str,
+    key: &str,
+    size: i64,
+    sha256: &str,
+    acl: Option<ACL>,
+  ) -> anyhow::Result<()> {
+    if size > MAX_COPY_OBJECT_SIZE {
+      return self.copy_object_multipart(bucket, key, size, bucket, key, acl, Some(sha256)).await;
+    }
+
+    self.client
+       .copy_object()
+       .copy_source(encode_copy_source(bucket, key))
+       .bucket(bucket)
+       .key(key)
+       .metadata_directive(MetadataDirective::Replace)
+       .metadata(SHA256_METADATA_KEY, sha256)
+       .set_acl(acl.map(object_acl))
+       .send()
+       .await?;
+
+    Ok(())
+  }
+
+  /// Copies a large object part by part, the upload is aborted if any of the parts fails. When
+  /// `sha256` is given it replaces the digest stored in the metadata of the source
+  #[allow(clippy::too_many_arguments)]
+  async fn copy_object_multipart(
+    &self,
+    source_bucket: &str,
+    source_key: &str,
+    size: i64,
+    bucket: &str,
+    key: &str,
+    acl: Option<ACL>,
+    sha256: Option<&str>,
+  ) -> anyhow::Result<()> {
+    // Multipart copies don't carry the metadata over, so it's taken from the source
+    let head = self.client
+       .head_object()
+       .bucket(source_bucket)
+       .key(source_key)
+       .send()
+       .await?;
+
+    let mut metadata = head.metadata().cloned();
+    if let Some(sha256) = sha256 {
+      metadata.get_or_insert_with(Default::default).insert(SHA256_METADATA_KEY.to_string(), sha256.to_string());
+    }
+
+    let upload = self.client
+       .create_multipart_upload()
+       .bucket(bucket)
+       .key(key)
+       .set_content_type(head.content_type().map(|t| t.to_string()))
+       .set_metadata(metadata)
+       .set_acl(acl.map(object_acl))
+       .send()
+       .await?;
+
+    let upload_id = upload.upload_id().unwrap_or_default().to_string();
+    let copy_source = encode_copy_source(source_bucket, source_key);
+    let max_parts = MAX_PARTS as i64;
+    let part_size = COPY_PART_SIZE.max((size + max_parts - 1) / max_parts);
+
+    let result = async {
+      let mut parts: Vec<CompletedPart> = Vec::new();
+      let mut start = 0;
+      let mut part_number = 1;
+
+      while start < size {
+        let end = (start + part_size).min(size) - 1;
+        let response = self.client
+           .upload_part_copy()
+           .bucket(bucket)
+           .key(key)
+           .upload_id(&upload_id)
+           .part_number(part_number)
+           .copy_source(&copy_source)
+           .copy_source_range(format!("bytes={}-{}", start, end))
+           .send()
+           .await?;
+
+        let e_tag = response.copy_part_result()
+           .and_then(|r| r.e_tag())
+           .map(|t| t.to_string());
+
+        parts.push(
+          CompletedPart::builder()
+             .part_number(part_number)
+             .set_e_tag(e_tag)
+             .build()
+        );
+
+        start = end + 1;
+        part_number += 1;
+      }
+
+      self.complete_multipart(bucket, key, &upload_id, parts).await
+    }.await;
+
+    if result.is_err() {
+      self.abort_multipart(bucket, key, &upload_id).await;
+    }
+
+    result
+  }
+
+  /// Writes an object to a temporary file next to the destination and renames it in place once
+  /// the whole body has been received, so a failed download never leaves a truncated file behind.
+  /// Objects larger than the part size are fetched with concurrent ranged requests
+  pub(crate) async fn download_object(
+    &self,
+    bucket: &str,
+    key: &str,
+    size: u64,
+    path: &Path,
+    transfer: &TransferOpts,
+    pb: &ProgressBar,
+  ) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+      if !parent.as_os_str().is_empty() {
+        tokio::fs::create_dir_all(parent).await?;
+      }
+    }
+
+    let temp = temp_path_for(path);
+
+    let result = async {
+      if size > transfer.part_size && transfer.concurrency > 1 {
+        self.download_ranged(bucket, key, size, &temp, transfer.part_size, transfer.concurrency, pb).await?;
+        return Ok(());
+      }
+
+      let response = self.client
+         .get_object()
+         .bucket(bucket)
+         .key(key)
+         .send()
+         .await?;
+
+      let expected = response.content_length();
+      let mut file = tokio::fs::File::create(&temp).await?;
+      let mut body = response.body.into_async_read();
+      let written = tokio::io::copy(&mut body, &mut file).await?;
+
+      if written as i64 != expected {
+        return Err(anyhow::anyhow!("Received {} of {} bytes for {}", written, expected, key));
+      }
+
+      file.sync_all().await?;
+
+      if !pb.is_finished() {
+        pb.inc(written);
+      }
+      Ok(())
+    }.await;
+
+    if let Err(e) = result {
+      let _ = tokio::fs::remove_file(&temp).await;
+      return Err(e);
+    }
+
+    tokio::fs::rename(&temp, path).await?;
+
+    Ok(())
+  }
+}
+
+/// Appends a relative key to the destination segments
+pub(crate) fn join_key(segments: &[String], relative_key: &str, delimiter: char) -> String {
+  let mut key = segments.join(delimiter.to_string().as_str());
+  if !key.is_empty() {
+    key.push(delimiter);
+  }
+  key.push_str(relative_key);
+  key
+}
+
+/// Builds the `x-amz-copy-source` value, the key is percent-encoded except for the slashes
+fn encode_copy_source(bucket: &str, key: &str) -> String {
+  let mut encoded = String::with_capacity(bucket.len() + key.len() + 1);
+  encoded.push_str(bucket);
+  encoded.push('/');
+  for byte in key.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+      _ => encoded.push_str(&format!("%{:02X}", byte)),
+    }
+  }
+  encoded
+}
+
+/// Rebuilds the local path of a key relative to the target directory, segments are split on the
+/// delimiter and "." or ".." segments are dropped so keys can't escape the target
+pub(crate) fn local_path_for(target: &Path, relative_key: &str, delimiter: char) -> PathBuf {
+  let mut path = target.to_path_buf();
+  for segment in relative_key.split(delimiter) {
+    if segment.is_empty() || segment == "." || segment == ".." {
+      continue;
+    }
+    path.push(segment);
+  }
+  path
+}
+
+/// Creates a byte based progress bar, it's finished right away when progress is not wanted
+pub(crate) fn transfer_progress_bar(total_bytes: u64, show_progress: bool) -> ProgressBar {
+  let pb = ProgressBar::new(total_bytes);
+  let style = ProgressStyle::with_template("{spinner} [{elapsed_precise}] [{bar:40}] {bytes}/{total_bytes} ({eta})")
+     .unwrap()
+     .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
+     .progress_chars("=> ");
+  pb.set_style(style);
+
+  if !show_progress {
+    pb.finish_and_clear();
+  }
+
+  pb
+}
+
+/// Converts a canned ACL to the type expected by object requests
+pub(crate) fn object_acl(acl: ACL) -> ObjectCannedAcl {
+  ObjectCannedAcl::from(acl.to_string().as_str())
+}
+
+/// Prints a line above the progress bar, or straight to stdout when there is none
+pub(crate) fn print_line(pb: &ProgressBar, line: String) {
+  if pb.is_finished() {
+    println!("{}", line);
+  } else {
+    pb.println(line);
+  }
+}
+
+/// Checks if the given path matches any of the exclude patterns
+pub(crate) fn is_excluded(exclude: &[regex::Regex], path: &str) -> bool {
+  exclude.iter().any(|r| r.is_match(path))
+}
+
+/// Fails when a recursive copy writes under its own source prefix, which would copy objects onto
+/// themselves or keep listing the copies it just made
+fn check_recursive_destination(source: &ParsedS3Url, destination: &ParsedS3Url, delimiter: char) -> anyhow::Result<()> {
+  let nested = source.bucket_name == destination.bucket_name
+     && destination.segments.starts_with(&source.segments);
+
+  match nested {
+    true => Err(anyhow::anyhow!(
+      "Cannot copy s3://{}{}{} into itself",
+      source.bucket_name, delimiter, source.segments.join(delimiter.to_string().as_str())
+    )),
+    false => Ok(()),
+  }
+}
+
+/// Fails when any (source key, size, destination key) copy has the same source and destination
+fn check_copies(source_bucket: &str, destination_bucket: &str, copies: &[(String, i64, String)]) -> anyhow::Result<()> {
+  match copies.iter().find(|(key, _, destination_key)| source_bucket == destination_bucket && key == destination_key) {
+    Some((key, _, _)) => Err(anyhow::anyhow!("Cannot copy s3://{}/{} onto itself", source_bucket, key)),
+    None => Ok(()),
+  }
+}
+
+/// Returns the key of a listed object relative to the listed prefix, which is what the exclude
+/// patterns are matched against. `None` when the key is excluded
+pub(crate) fn relative_key<'a>(key: &'a str, prefix: &str, delimiter: char, exclude: &[regex::Regex]) -> Option<&'a str> {
+  let relative = key.strip_prefix(prefix).unwrap_or(key).trim_start_matches(delimiter);
+  match is_excluded(exclude, relative) {
+    true => None,
+    false => Some(relative),
+  }
+}
+
+/// Returns path of the file relative to the base directory, for a single file it's the file name
+fn relative_path(base: &Path, file: &Path) -> String {
+  match file.strip_prefix(base) {
+    Ok(p) if !p.as_os_str().is_empty() => p.to_string_lossy().to_string(),
+    _ => file.file_name().unwrap_or_default().to_string_lossy().to_string(),
+  }
+}
+
+/// Maps a local file to an object key under the destination, path components are joined with the delimiter
+fn object_key_for(
+  base: &Path,
+  file: &Path,
+  destination: &ParsedS3Url,
+  exact_key: bool,
+  delimiter: char,
+) -> String {
+  let mut segments = destination.segments.clone();
+
+  if !exact_key {
+    match file.strip_prefix(base) {
+      Ok(p) if !p.as_os_str().is_empty() => {
+        for component in p.components() {
+          segments.push(component.as_os_str().to_string_lossy().to_string());
+        }
+      }
+      _ => segments.push(file.file_name().unwrap_or_default().to_string_lossy().to_string()),
+    }
+  }
+
+  segments.join(delimiter.to_string().as_str())
+}
+
+#[cfg(test)]
+mod transfer_tests {
+  use super::*;
+
+  #[test]
+  fn test_object_key_for() {
+    let destination = ParsedS3Url::parse_from(&String::from("s3://backups/nightly/"), &'/').unwrap();
+
+    let key = object_key_for(Path::new("./dir"), Path::new("./dir/a/b.txt"), &destination, false, '/');
+    assert_eq!(key, "nightly/a/b.txt");
+
+    let key = object_key_for(Path::new("./LICENSE"), Path::new("./LICENSE"), &destination, false, '/');
+    assert_eq!(key, "nightly/LICENSE");
+
+    let destination = ParsedS3Url::parse_from(&String::from("s3://backups/nightly/renamed"), &'/').unwrap();
+    let key = object_key_for(Path::new("./LICENSE"), Path::new("./LICENSE"), &destination, true, '/');
+    assert_eq!(key, "nightly/renamed");
+  }
+
+  #[test]
+  fn test_local_path_for() {
+    assert_eq!(local_path_for(Path::new("out"), "a/b.txt", '/'), PathBuf::from("out/a/b.txt"));
+    assert_eq!(local_path_for(Path::new("out"), "a:b.txt", ':'), PathBuf::from("out/a/b.txt"));
+    assert_eq!(local_path_for(Path::new("out"), "../../etc/passwd", '/'), PathBuf::from("out/etc/passwd"));
+  }
+
+  #[test]
+  fn test_join_key() {
+    assert_eq!(join_key(&[String::from("archive")], "2023/a.log", '/'), "archive/2023/a.log");
+    assert_eq!(join_key(&[], "a.log", '/'), "a.log");
+  }
+
+  #[test]
+  fn test_encode_copy_source() {
+    assert_eq!(encode_copy_source("logs", "2023/jan/a.log"), "logs/2023/jan/a.log");
+    assert_eq!(encode_copy_source("logs", "my file+1.txt"), "logs/my%20file%2B1.txt");
+  }
+
+  #[test]
+  fn test_relative_path() {
+    assert_eq!(relative_path(Path::new("src"), Path::new("src/s3/mod.rs")), "s3/mod.rs");
+    assert_eq!(relative_path(Path::new("LICENSE"), Path::new("LICENSE")), "LICENSE");
+  }
+
+  #[test]
+  fn test_check_recursive_destination() {
+    let source = ParsedS3Url::parse_from(&String::from("s3://b/x"), &'/').unwrap();
+    let nested = |to: &str| {
+      let destination = ParsedS3Url::parse_from(&String::from(to), &'/').unwrap();
+      check_recursive_destination(&source, &destination, '/').is_err()
+    };
+
+    assert!(nested("s3://b/x/"));
+    assert!(nested("s3://b/x/y"));
+    assert!(!nested("s3://b/xy"));
+    assert!(!nested("s3://b/"));
+    assert!(!nested("s3://other/x/"));
+
+    let whole_bucket = ParsedS3Url::parse_from(&String::from("s3://b"), &'/').unwrap();
+    let destination = ParsedS3Url::parse_from(&String::from("s3://b/backup/"), &'/').unwrap();
+    assert!(check_recursive_destination(&whole_bucket, &destination, '/').is_err());
+  }
+
+  #[test]
+  fn test_check_copies() {
+    let copies = vec![(String::from("a.txt"), 1, String::from("a.txt"))];
+    assert!(check_copies("b", "b", &copies).is_err());
+    assert!(check_copies("b", "other", &copies).is_ok());
+
+    let copies = vec![(String::from("a.txt"), 1, String::from("b.txt"))];
+    assert!(check_copies("b", "b", &copies).is_ok());
+  }
+
+  #[test]
+  fn test_relative_key() {
+    let exclude = vec![regex::Regex::new(r"\.tmp$").unwrap()];
+    assert_eq!(relative_key("logs/2023/a.log", "logs/", '/', &exclude), Some("2023/a.log"));
+    assert_eq!(relative_key("logs/2023/a.log", "logs", '/', &exclude), Some("2023/a.log"));
+    assert_eq!(relative_key("logs/2023/a.tmp", "logs/", '/', &exclude), None);
+    assert_eq!(relative_key("other/a.log", "logs/", '/', &[]), Some("other/a.log"));
+  }
+}
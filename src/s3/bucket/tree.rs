@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use aws_sdk_s3::types::Object;
+use console::Emoji;
+use tokio_stream::StreamExt;
+
+use crate::commands::tree::TreeOpts;
+use crate::s3::bucket::Bucket;
+use crate::s3::bucket::output::format_size;
+use crate::s3::content::{S3Directory, S3File};
+use crate::s3::ParsedS3Url;
+
+impl Bucket {
+  /// Lists every object under the path into a tree of its directories
+  pub async fn tree(&self, opts: TreeOpts) -> anyhow::Result<TreeNode> {
+    let parsed = ParsedS3Url::parse_from(&opts.path, &opts.delimiter)?;
+    let prefix = parsed.segments.join(opts.delimiter.to_string().as_str());
+
+    let mut root = TreeNode::new(opts.path.clone());
+    let mut pages = self.sharded_object_pages(&opts.path, &opts.delimiter, opts.list_concurrency)?;
+    while let Some(page) = pages.next().await {
+      for object in page? {
+        let key = object.key().unwrap_or_default();
+        let relative = key.strip_prefix(prefix.as_str()).unwrap_or(key).trim_start_matches(opts.delimiter);
+        let segments = relative.split(opts.delimiter).collect::<Vec<_>>();
+        root.insert(&segments, &object, opts.depth);
+      }
+    }
+
+    Ok(root)
+  }
+}
+
+/// A directory of the tree, its [S3Directory] holds the totals of everything below it
+#[derive(Debug)]
+pub struct TreeNode {
+  pub directory: S3Directory,
+  pub directories: BTreeMap<String, TreeNode>,
+  /// Sizes of the objects right in the directory, by name
+  pub objects: BTreeMap<String, i64>,
+}
+
+impl TreeNode {
+  pub fn new(name: String) -> Self {
+    Self {
+      directory: S3Directory::new(name),
+      directories: BTreeMap::new(),
+      objects: BTreeMap::new(),
+    }
+  }
+
+  /// Adds an object given the segments of its key below this directory. Once `depth` levels
+  /// down, objects are only counted towards the directory they're in. A directory marker such
+  /// as `logs/` creates its directory but isn't counted as an object, it isn't shown as one
+  pub fn insert(&mut self, segments: &[&str], object: &Object, depth: Option<usize>) {
+    if segments.last().is_some_and(|name| !name.is_empty()) {
+      self.directory.add_file(S3File {
+        last_modified: object.last_modified().cloned().unwrap_or_else(|| aws_sdk_s3::primitives::DateTime::from_secs(0)),
+        size: object.size(),
+        key: object.key().unwrap_or_default().to_string(),
+      });
+    }
+
+    if depth == Some(0) {
+      return;
+    }
+
+    match segments {
+      [] | [""] => {}
+      [name] => {
+        self.objects.insert(name.to_string(), object.size());
+      }
+      [name, rest @ ..] => {
+        self.directories
+           .entry(name.to_string())
+           .or_insert_with(|| TreeNode::new(name.to_string()))
+           .insert(rest, object, depth.map(|d| d - 1));
+      }
+    }
+  }
+
+  /// Renders the tree like `tree`, directories come before objects and both are sorted by name
+  pub fn render(&self, delimiter: char, human_size: bool) -> Vec<String> {
+    let mut lines = vec![format!("{} {}", self.directory.name, self.totals(human_size))];
+    self.render_children("", delimiter, human_size, &mut lines);
+    lines
+  }
+
+  fn render_children(&self, indent: &str, delimiter: char, human_size: bool, lines: &mut Vec<String>) {
+    let count = self.directories.len() + self.objects.len();
+
+    for (index, (name, node)) in self.directories.iter().enumerate() {
+      let last = index + 1 == count;
+      lines.push(format!("{}{}{}{} {}", indent, branch(last), name, delimiter, node.totals(human_size)));
+
+      let indent = match last {
+        true => format!("{}    ", indent),
+        false => format!("{}{}", indent, TRUNK),
+      };
+      node.render_children(&indent, delimiter, human_size, lines);
+    }
+
+    for (index, (name, size)) in self.objects.iter().enumerate() {
+      let last = self.directories.len() + index + 1 == count;
+      lines.push(format!("{}{}{} ({})", indent, branch(last), name, format_size(*size as usize, human_size)));
+    }
+  }
+
+  /// Number of directories and objects shown in the tree, not counting the root
+  pub fn counts(&self) -> (usize, usize) {
+    self.directories.values().fold((self.directories.len(), self.objects.len()), |(dirs, objects), node| {
+      let (node_dirs, node_objects) = node.counts();
+      (dirs + node_dirs, objects + node_objects)
+    })
+  }
+
+  fn totals(&self, human_size: bool) -> String {
+    format!("({}, {} objects)", format_size(self.directory.size(), human_size), self.directory.len())
+  }
+}
+
+static LAST_BRANCH: Emoji<'_, '_> = Emoji("└── ", "`-- ");
+static BRANCH: Emoji<'_, '_> = Emoji("├── ", "|-- ");
+static TRUNK: Emoji<'_, '_> = Emoji("│   ", "|   ");
+
+fn branch(last: bool) -> Emoji<'static, 'static> {
+  match last {
+    true => LAST_BRANCH,
+    false => BRANCH,
+  }
+}
+
+#[cfg(test)]
+mod tree_tests {
+  use super::*;
+
+  fn insert(root: &mut TreeNode, key: &str, size: i64, depth: Option<usize>) {
+    let object = Object::builder().key(key).size(size).build();
+    root.insert(&key.split('/').collect::<Vec<_>>(), &object, depth);
+  }
+
+  #[test]
+  fn test_render_tree() {
+    let mut root = TreeNode::new(String::from("s3://bucket/"));
+    insert(&mut root, "logs/2023/app.log", 10, None);
+    insert(&mut root, "logs/2024/", 0, None);
+    insert(&mut root, "logs/readme.md", 1, None);
+    insert(&mut root, "index.html", 5, None);
+
+    // The branches fall back to ASCII when the terminal can't show them
+    let expected = |lines: Vec<&str>| lines
+       .into_iter()
+       .map(|l| l.replace("├── ", &BRANCH.to_string()).replace("└── ", &LAST_BRANCH.to_string()).replace("│   ", &TRUNK.to_string()))
+       .collect::<Vec<_>>();
+
+    assert_eq!(root.render('/', false), expected(vec![
+      "s3://bucket/ (16, 3 objects)",
+      "├── logs/ (11, 2 objects)",
+      "│   ├── 2023/ (10, 1 objects)",
+      "│   │   └── app.log (10)",
+      "│   ├── 2024/ (0, 0 objects)",
+      "│   └── readme.md (1)",
+      "└── index.html (5)",
+    ]));
+
+    // The footer counts the same objects as the root
+    assert_eq!(root.counts(), (3, 3));
+
+    let mut shallow = TreeNode::new(String::from("s3://bucket/"));
+    insert(&mut shallow, "logs/2023/app.log", 10, Some(1));
+    insert(&mut shallow, "index.html", 5, Some(1));
+
+    assert_eq!(shallow.render('/', false), expected(vec![
+      "s3://bucket/ (15, 2 objects)",
+      "├── logs/ (10, 1 objects)",
+      "└── index.html (5)",
+    ]));
+  }
+}
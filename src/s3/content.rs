@@ -1,46 +1,52 @@
-use std::collections::HashMap;
 use std::time::SystemTime;
 
 use crate::utc_datetime;
 
+/// A "directory" of a listing, the files in it are only counted so it takes the same memory
+/// no matter how many objects share the prefix
 #[derive(Debug)]
 pub struct S3Directory {
   pub name: String,
-  contents: HashMap<String, S3File>,
+  size: usize,
+  files: usize,
+  last_modified: Option<aws_sdk_s3::primitives::DateTime>,
 }
 
 impl S3Directory {
   pub fn new(name: String) -> Self {
     Self {
       name,
-      contents: HashMap::new(),
+      size: 0,
+      files: 0,
+      last_modified: None,
     }
   }
 
   pub fn add_file(&mut self, file: S3File) {
-    self.contents.insert(file.key.clone(), file);
+    self.size += file.size as usize;
+    self.files += 1;
+    if self.last_modified.is_none_or(|last_modified| file.last_modified > last_modified) {
+      self.last_modified = Some(file.last_modified);
+    }
   }
 
-  pub fn file_exists(&self, key: &String) -> bool {
-    self.contents.contains_key(key)
+  pub fn size(&self) -> usize {
+    self.size
   }
 
-  pub fn size(&self) -> usize {
-    let mut size = 0;
-    for (_, file) in self.contents.iter() {
-      size += file.size as usize;
-    }
-    size
+  /// Number of files in the directory
+  pub fn len(&self) -> usize {
+    self.files
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.files == 0
+  }
+
+  /// Newest modification time of the files in the directory
   pub fn last_modified(&self) -> aws_sdk_s3::primitives::DateTime {
-    let mut last_modified = aws_sdk_s3::primitives::DateTime::from(SystemTime::now());
-    for (_, file) in self.contents.iter() {
-      if file.last_modified > last_modified {
-        last_modified = file.last_modified;
-      }
-    }
-    last_modified
+    self.last_modified
+       .unwrap_or_else(|| aws_sdk_s3::primitives::DateTime::from(SystemTime::UNIX_EPOCH))
   }
 }
 
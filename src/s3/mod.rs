@@ -55,6 +55,10 @@ impl ParsedS3Url {
     })
   }
 
+  pub fn bucket_name(&self) -> &str {
+    &self.bucket_name
+  }
+
   // https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-keys.html
   // The following character sets are generally safe for use in key names.
   //
@@ -124,6 +128,7 @@ mod tests_parsed_3url {
   }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ACL {
   Private,
   PublicRead,
@@ -146,4 +151,42 @@ impl ACL {
       ACL::BucketOwnerFC => String::from("bucket-owner-full-control"),
     }
   }
+
+  /// Finds a canned ACL by its name, e.g. `public-read`
+  pub fn from_name(name: &str) -> Option<ACL> {
+    Self::all().into_iter().find(|acl| acl.to_string() == name)
+  }
+
+  pub fn all() -> Vec<ACL> {
+    vec![
+      ACL::Private,
+      ACL::PublicRead,
+      ACL::PublicReadWrite,
+      ACL::AuthenticatedRead,
+      ACL::AWSExecRead,
+      ACL::BucketOwnerRead,
+      ACL::BucketOwnerFC,
+    ]
+  }
+
+  /// Whether the ACL can be applied to a bucket, the rest only apply to objects
+  pub fn is_bucket_acl(&self) -> bool {
+    matches!(self, ACL::Private | ACL::PublicRead | ACL::PublicReadWrite | ACL::AuthenticatedRead)
+  }
+}
+
+#[cfg(test)]
+mod tests_acl {
+  use super::*;
+
+  #[test]
+  fn test_acl_from_name() {
+    assert_eq!(ACL::from_name("public-read"), Some(ACL::PublicRead));
+    assert_eq!(ACL::from_name("bucket-owner-full-control"), Some(ACL::BucketOwnerFC));
+    assert_eq!(ACL::from_name("public"), None);
+
+    for acl in ACL::all() {
+      assert_eq!(ACL::from_name(&acl.to_string()), Some(acl));
+    }
+  }
 }